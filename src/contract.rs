@@ -1,15 +1,21 @@
 use cosmwasm_std::{
-    entry_point, DepsMut, Env, MessageInfo, Response, StdResult, Uint128, Addr, BankMsg, Coin, to_json_binary, Deps, Binary, CosmosMsg
+    entry_point, DepsMut, Env, MessageInfo, Response, StdResult, Uint128, Addr, BankMsg, Coin, to_json_binary, Deps, Binary, CosmosMsg, Storage, Empty, Int128, Api, StdError
 };
 use crate::error::ContractError;
-use crate::msg::{InstantiateMsg, ExecuteMsg, MigrateMsg};
-use crate::state::{Config, CONFIG, MARKET_COUNT, ORDER_COUNT, MATCHED_BET_COUNT, Market, MARKETS, PROPOSALS, ResolutionProposal, ProposalStatus, MarketStatus, Dispute, DisputeStatus, WHITELISTED_ADDRESSES, OrderSide, ORDERS, Order, OrderStatus, MATCHED_BETS, MatchedBet, VOTES, VOTE_COUNTS, Vote, DISPUTES, MarketStatistics};
+use crate::msg::{InstantiateMsg, ExecuteMsg, MigrateMsg, ConfigUpdate};
+use crate::state::{Config, CONFIG, VoterRewardPool, VoterRewardResponse, VOTER_REWARD_POOLS, VOTER_REWARD_WEIGHTS, VOTER_REWARDS_CLAIMED, EscalationConfig, DisputeRound, DISPUTE_ROUNDS, StakingConfig, StakeResponse, Unbonding, STAKES, TOTAL_STAKED, UNBONDING, VotesResponse, VoteMode, VOTE_COMMITS, DisputeRules, VOID_OUTCOME, BondSplit, PauseFlags, PauseScope, PendingAdmin, PENDING_ADMIN, Role, ROLES, MARKET_COUNT, ORDER_COUNT, MATCHED_BET_COUNT, Market, MARKETS, OptionStatus, PROPOSALS, ResolutionProposal, ProposalStatus, MarketStatus, Dispute, DisputeStatus, WHITELISTED_ADDRESSES, OrderSide, ORDERS, USER_ORDERS, ORDER_BOOK, OrderBookKey, Order, OrderStatus, TimeInForce, MATCHED_BETS, USER_MATCHED_BETS, MatchedBet, VOTES, VOTE_COUNTS, Vote, DISPUTES, MarketStatistics, MarketFees, MARKET_FEES, OrderBook, PriceLevel, LAST_MATCHED_ODDS, BALANCES, Position, POSITIONS, PositionResponse, PositionsResponse, CashOutQuote};
 use crate::msg::OrderType;
-use std::str::FromStr;
 use crate::msg::QueryMsg;
+use crate::state::{LEGACY_CONFIG, LEGACY_MARKETS, LEGACY_ORDERS, LEGACY_USER_ORDERS, LEGACY_USER_MATCHED_BETS};
+use cw2::set_contract_version;
 use cw_storage_plus::Bound;
 use regex::Regex;
+use semver::Version;
+use sha2::{Digest, Sha256};
 
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:exchange-v2";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -21,6 +27,9 @@ pub fn instantiate(
     // Fix Bug ID #13: Validate non-zero addresses
     validate_non_zero_addr(&msg.admin)?;
     validate_non_zero_addr(&msg.treasury)?;
+    if let Some(guardian) = &msg.guardian {
+        validate_non_zero_addr(guardian)?;
+    }
 
     // Fix Bug #14 & #15: Validate both periods
     validate_period(msg.challenging_period, "challenging_period")?;
@@ -29,6 +38,21 @@ pub fn instantiate(
     // Fix Bug #16: Validate min_bet
     validate_min_bet(msg.min_bet)?;
 
+    validate_platform_fee(msg.platform_fee)?;
+    validate_denom(&msg.token_denom)?;
+    let bond_split = msg.bond_split.unwrap_or_default();
+    validate_bond_split(&bond_split)?;
+    let dispute_rules = msg.dispute_rules.unwrap_or_default();
+    validate_dispute_rules(&dispute_rules)?;
+    let vote_mode = msg.vote_mode.unwrap_or_default();
+    validate_vote_mode(&vote_mode)?;
+    let staking = msg.staking.unwrap_or_default();
+    validate_staking(&staking)?;
+    let escalation = msg.escalation.unwrap_or_default();
+    validate_escalation(&escalation)?;
+    let voter_fee_share = msg.voter_fee_share.unwrap_or(0);
+    validate_voter_fee_share(voter_fee_share)?;
+
     let config = Config {
         admin: msg.admin,
         token_denom: msg.token_denom,
@@ -38,10 +62,20 @@ pub fn instantiate(
         voting_period: msg.voting_period,
         min_bet: msg.min_bet,
         whitelist_enabled: msg.whitelist_enabled,
+        guardian: msg.guardian,
+        paused: PauseFlags::default(),
+        bond_split,
+        dispute_rules,
+        vote_mode,
+        staking,
+        escalation,
+        voter_fee_share,
     };
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     CONFIG.save(deps.storage, &config)?;
     MARKET_COUNT.save(deps.storage, &0u64)?;
+    TOTAL_STAKED.save(deps.storage, &Uint128::zero())?;
     ORDER_COUNT.save(deps.storage, &0u64)?;
     MATCHED_BET_COUNT.save(deps.storage, &0u64)?;
 
@@ -60,8 +94,7 @@ pub fn instantiate(
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
-    field: String,
-    value: String,
+    update: ConfigUpdate,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
 
@@ -70,52 +103,380 @@ pub fn update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    match field.as_str() {
-        "admin" => {
-            let new_admin = deps.api.addr_validate(&value)?;
-            // Fix Bug ID #13: Validate non-zero address
-            validate_non_zero_addr(&new_admin)?;
-            config.admin = new_admin;
-        },
-        "token_denom" => config.token_denom = value.clone(),
-        "platform_fee" => config.platform_fee = value.parse::<Uint128>()?,
-        "treasury" => {
-            let new_treasury = deps.api.addr_validate(&value)?;
-            // Fix Bug ID #13: Validate non-zero address
-            validate_non_zero_addr(&new_treasury)?;
-            config.treasury = new_treasury;
-        },        
-        "challenging_period" => {
-            let period = u64::from_str(&value)
-                .map_err(|_| ContractError::InvalidField { field: field.clone() })?;
-            // Fix Bug #14: Validate challenging period
-            validate_period(period, &field)?;
-            config.challenging_period = period;
-        },
-        "voting_period" => {
-            let period = u64::from_str(&value)
-                .map_err(|_| ContractError::InvalidField { field: field.clone() })?;
-            // Fix Bug #15: Validate voting period
-            validate_period(period, &field)?;
-            config.voting_period = period;
-        },
-        "min_bet" => {
-            let min_bet = Uint128::from_str(&value)
-                .map_err(|_| ContractError::InvalidField { field: field.clone() })?;
-            // Fix Bug #16: Validate min_bet
-            validate_min_bet(min_bet)?;
-            config.min_bet = min_bet;
-        },        
-        "whitelist_enabled" => config.whitelist_enabled = bool::from_str(&value).map_err(|_| ContractError::InvalidField { field: field.clone() })?,
-        _ => return Err(ContractError::InvalidField { field: field.to_string() }),
+    let attributes = apply_config_update(deps.storage, deps.api, &mut config, update)?;
+    if attributes.is_empty() {
+        return Err(ContractError::EmptyConfigUpdate {});
     }
 
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new()
         .add_attribute("method", "update_config")
-        .add_attribute("field", field)
-        .add_attribute("value", value))
+        .add_attributes(attributes))
+}
+
+/// Validates and applies the fields set in `update`, returning one attribute per changed field.
+/// Every field is validated before the caller saves the config, so the update applies atomically.
+fn apply_config_update(storage: &dyn Storage, api: &dyn Api, config: &mut Config, update: ConfigUpdate) -> Result<Vec<(&'static str, String)>, ContractError> {
+    let mut attributes = vec![];
+
+    if let Some(token_denom) = update.token_denom {
+        validate_denom(&token_denom)?;
+        if token_denom != config.token_denom {
+            // The denom cannot change while the contract owes funds in the current one
+            if holds_funds(storage)? {
+                return Err(ContractError::DenomInUse {});
+            }
+            attributes.push(("token_denom", token_denom.clone()));
+            config.token_denom = token_denom;
+        }
+    }
+    if let Some(platform_fee) = update.platform_fee {
+        validate_platform_fee(platform_fee)?;
+        if platform_fee != config.platform_fee {
+            attributes.push(("platform_fee", platform_fee.to_string()));
+            config.platform_fee = platform_fee;
+        }
+    }
+    if let Some(treasury) = update.treasury {
+        let treasury = api.addr_validate(treasury.as_str())?;
+        // Fix Bug ID #13: Validate non-zero address
+        validate_non_zero_addr(&treasury)?;
+        if treasury != config.treasury {
+            attributes.push(("treasury", treasury.to_string()));
+            config.treasury = treasury;
+        }
+    }
+    if let Some(challenging_period) = update.challenging_period {
+        // Fix Bug #14: Validate challenging period
+        validate_period(challenging_period, "challenging_period")?;
+        if challenging_period != config.challenging_period {
+            attributes.push(("challenging_period", challenging_period.to_string()));
+            config.challenging_period = challenging_period;
+        }
+    }
+    if let Some(voting_period) = update.voting_period {
+        // Fix Bug #15: Validate voting period
+        validate_period(voting_period, "voting_period")?;
+        if voting_period != config.voting_period {
+            attributes.push(("voting_period", voting_period.to_string()));
+            config.voting_period = voting_period;
+        }
+    }
+    if let Some(min_bet) = update.min_bet {
+        // Fix Bug #16: Validate min_bet
+        validate_min_bet(min_bet)?;
+        if min_bet != config.min_bet {
+            attributes.push(("min_bet", min_bet.to_string()));
+            config.min_bet = min_bet;
+        }
+    }
+    if let Some(whitelist_enabled) = update.whitelist_enabled {
+        if whitelist_enabled != config.whitelist_enabled {
+            attributes.push(("whitelist_enabled", whitelist_enabled.to_string()));
+            config.whitelist_enabled = whitelist_enabled;
+        }
+    }
+    if update.clear_guardian == Some(true) {
+        if update.guardian.is_some() {
+            return Err(ContractError::ConflictingGuardianUpdate {});
+        }
+        if config.guardian.is_some() {
+            attributes.push(("guardian", "none".to_string()));
+            config.guardian = None;
+        }
+    }
+    if let Some(guardian) = update.guardian {
+        let guardian = api.addr_validate(guardian.as_str())?;
+        validate_non_zero_addr(&guardian)?;
+        if config.guardian.as_ref() != Some(&guardian) {
+            attributes.push(("guardian", guardian.to_string()));
+            config.guardian = Some(guardian);
+        }
+    }
+    if let Some(bond_split) = update.bond_split {
+        validate_bond_split(&bond_split)?;
+        if bond_split != config.bond_split {
+            attributes.push(("bond_split", format!("{}:{}:{}", bond_split.winner, bond_split.voters, bond_split.treasury)));
+            config.bond_split = bond_split;
+        }
+    }
+    if let Some(dispute_rules) = update.dispute_rules {
+        validate_dispute_rules(&dispute_rules)?;
+        if dispute_rules != config.dispute_rules {
+            attributes.push(("dispute_rules", format!(
+                "quorum={},supermajority={},tie={},no_quorum={}",
+                dispute_rules.quorum, dispute_rules.supermajority, dispute_rules.tie_rule, dispute_rules.no_quorum_rule,
+            )));
+            config.dispute_rules = dispute_rules;
+        }
+    }
+    if let Some(vote_mode) = update.vote_mode {
+        validate_vote_mode(&vote_mode)?;
+        if vote_mode != config.vote_mode {
+            attributes.push(("vote_mode", vote_mode.to_string()));
+            config.vote_mode = vote_mode;
+        }
+    }
+    if let Some(staking) = update.staking {
+        validate_staking(&staking)?;
+        if staking != config.staking {
+            attributes.push(("staking", format!(
+                "unbonding_period={},stake_weighted_voting={},slash_rate={}",
+                staking.unbonding_period, staking.stake_weighted_voting, staking.slash_rate,
+            )));
+            config.staking = staking;
+        }
+    }
+    if let Some(escalation) = update.escalation {
+        validate_escalation(&escalation)?;
+        if escalation != config.escalation {
+            attributes.push(("escalation", format!(
+                "max_appeals={},bond_multiplier={},appeal_period={}",
+                escalation.max_appeals, escalation.bond_multiplier, escalation.appeal_period,
+            )));
+            config.escalation = escalation;
+        }
+    }
+    if let Some(voter_fee_share) = update.voter_fee_share {
+        validate_voter_fee_share(voter_fee_share)?;
+        if voter_fee_share != config.voter_fee_share {
+            attributes.push(("voter_fee_share", voter_fee_share.to_string()));
+            config.voter_fee_share = voter_fee_share;
+        }
+    }
+
+    Ok(attributes)
+}
+
+/// Whether the contract still owes funds in `token_denom`: credited balances, stakes, markets that
+/// are not settled yet, and the unredeemed bets, bonds, fees and voter rewards of settled ones
+fn holds_funds(storage: &dyn Storage) -> StdResult<bool> {
+    for item in BALANCES.range(storage, None, None, cosmwasm_std::Order::Ascending) {
+        if !item?.1.is_zero() {
+            return Ok(true);
+        }
+    }
+    if !TOTAL_STAKED.may_load(storage)?.unwrap_or_default().is_zero()
+        || UNBONDING.keys(storage, None, None, cosmwasm_std::Order::Ascending).next().is_some()
+    {
+        return Ok(true);
+    }
+    for item in MARKETS.range(storage, None, None, cosmwasm_std::Order::Ascending) {
+        if market_holds_funds(storage, &item?.1)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn market_holds_funds(storage: &dyn Storage, market: &Market) -> StdResult<bool> {
+    // Unsettled markets hold orders, bets, bonds and the resolution reward
+    if !matches!(market.status, MarketStatus::Resolved | MarketStatus::Canceled) {
+        return Ok(true);
+    }
+
+    for item in MATCHED_BETS.idx.market.prefix(market.id).range(storage, None, None, cosmwasm_std::Order::Ascending) {
+        if !item?.1.redeemed {
+            return Ok(true);
+        }
+    }
+    if MARKET_FEES.may_load(storage, market.id)?.is_some_and(|fees| !fees.pending.is_zero()) {
+        return Ok(true);
+    }
+
+    // Winning bonds are held until their owner redeems them
+    if let Some(proposal) = PROPOSALS.may_load(storage, market.id)? {
+        if market.result == Some(proposal.proposed_result) && !proposal.bond_redeemed {
+            return Ok(true);
+        }
+        for round in load_dispute_rounds(storage, market.id, &proposal)? {
+            if market.result != Some(round.challenged_outcome) && !round.bond_redeemed {
+                return Ok(true);
+            }
+        }
+    }
+
+    if let Some(pool) = VOTER_REWARD_POOLS.may_load(storage, market.id)? {
+        for item in VOTER_REWARD_WEIGHTS.prefix(market.id).range(storage, None, None, cosmwasm_std::Order::Ascending) {
+            let (voter, weight) = item?;
+            let claimed = VOTER_REWARDS_CLAIMED.may_load(storage, (market.id, voter))?.unwrap_or_default();
+            if pool.total().multiply_ratio(weight, pool.winning_weight) > claimed {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+pub fn propose_new_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_admin: Addr,
+    expires_at: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only admin can propose a new admin
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let new_admin = deps.api.addr_validate(new_admin.as_str())?;
+    validate_non_zero_addr(&new_admin)?;
+
+    if expires_at.is_some_and(|expires_at| expires_at <= env.block.time.seconds()) {
+        return Err(ContractError::InvalidExpiry {});
+    }
+
+    // A new proposal replaces any pending one
+    PENDING_ADMIN.save(deps.storage, &PendingAdmin { new_admin: new_admin.clone(), expires_at })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "propose_new_admin")
+        .add_attribute("new_admin", new_admin.to_string())
+        .add_attribute("expires_at", expires_at.map_or("never".to_string(), |t| t.to_string())))
+}
+
+pub fn accept_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+
+    // Only the proposed address can accept the transfer
+    if info.sender != pending.new_admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if pending.is_expired(env.block.time.seconds()) {
+        return Err(ContractError::AdminTransferExpired {});
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_admin = std::mem::replace(&mut config.admin, pending.new_admin);
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_admin")
+        .add_attribute("previous_admin", previous_admin.to_string())
+        .add_attribute("admin", config.admin.to_string()))
+}
+
+pub fn cancel_admin_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only admin can cancel a pending transfer
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "cancel_admin_transfer")
+        .add_attribute("new_admin", pending.new_admin.to_string()))
+}
+
+pub fn set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    scope: Option<PauseScope>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Only admin or guardian can pause and unpause
+    if info.sender != config.admin && config.guardian.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let scopes = scope.map_or(PauseScope::ALL.to_vec(), |scope| vec![scope]);
+    for scope in &scopes {
+        config.paused.set(*scope, paused);
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    let method = if paused { "pause" } else { "unpause" };
+    Ok(Response::new()
+        .add_attribute("method", method)
+        .add_attributes(scopes.iter().map(|scope| ("scope", scope.to_string()))))
+}
+
+fn ensure_not_paused(config: &Config, scope: PauseScope) -> Result<(), ContractError> {
+    if config.paused.is_paused(scope) {
+        return Err(ContractError::Paused { scope: scope.to_string() });
+    }
+    Ok(())
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only admin can grant roles
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let address = deps.api.addr_validate(address.as_str())?;
+    validate_non_zero_addr(&address)?;
+
+    if ROLES.has(deps.storage, (role.key(), address.clone())) {
+        return Err(ContractError::RoleAlreadyGranted {});
+    }
+    ROLES.save(deps.storage, (role.key(), address.clone()), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("method", "grant_role")
+        .add_attribute("role", role.to_string())
+        .add_attribute("address", address.to_string()))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only admin can revoke roles
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let address = deps.api.addr_validate(address.as_str())?;
+
+    if !ROLES.has(deps.storage, (role.key(), address.clone())) {
+        return Err(ContractError::RoleNotGranted {});
+    }
+    ROLES.remove(deps.storage, (role.key(), address.clone()));
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("role", role.to_string())
+        .add_attribute("address", address.to_string()))
+}
+
+fn has_role(storage: &dyn Storage, config: &Config, address: &Addr, role: Role) -> bool {
+    // The admin holds every role
+    *address == config.admin || ROLES.has(storage, (role.key(), address.clone()))
 }
 
 fn validate_non_zero_addr(addr: &Addr) -> Result<(), ContractError> {
@@ -139,20 +500,102 @@ fn validate_min_bet(amount: Uint128) -> Result<(), ContractError> {
     Ok(())
 }
 
+// Same format as native Cosmos SDK denoms, including ibc/ and factory/ denoms
+fn validate_denom(denom: &str) -> Result<(), ContractError> {
+    let re = Regex::new(r"^[a-zA-Z][a-zA-Z0-9/:._-]{2,127}$").unwrap();
+    if !re.is_match(denom) {
+        return Err(ContractError::InvalidDenom {});
+    }
+    Ok(())
+}
+
+fn validate_bond_split(split: &BondSplit) -> Result<(), ContractError> {
+    if split.winner + split.voters + split.treasury != 10000 {
+        return Err(ContractError::InvalidBondSplit {});
+    }
+    Ok(())
+}
+
+// slash_rate is expressed in basis points
+fn validate_staking(staking: &StakingConfig) -> Result<(), ContractError> {
+    validate_period(staking.unbonding_period, "unbonding_period")?;
+    if staking.slash_rate > 10000 {
+        return Err(ContractError::InvalidField { field: "slash_rate".to_string() });
+    }
+    Ok(())
+}
+
+// voter_fee_share is expressed in basis points
+fn validate_voter_fee_share(share: u64) -> Result<(), ContractError> {
+    if share > 10000 {
+        return Err(ContractError::InvalidField { field: "voter_fee_share".to_string() });
+    }
+    Ok(())
+}
+
+fn validate_escalation(escalation: &EscalationConfig) -> Result<(), ContractError> {
+    validate_period(escalation.appeal_period, "appeal_period")?;
+    if escalation.bond_multiplier == 0 {
+        return Err(ContractError::InvalidBondMultiplier {});
+    }
+    Ok(())
+}
+
+fn validate_vote_mode(mode: &VoteMode) -> Result<(), ContractError> {
+    if let VoteMode::CommitReveal { reveal_period } = mode {
+        validate_period(*reveal_period, "reveal_period")?;
+    }
+    Ok(())
+}
+
+// supermajority is expressed in basis points
+fn validate_dispute_rules(rules: &DisputeRules) -> Result<(), ContractError> {
+    if rules.supermajority > 10000 {
+        return Err(ContractError::InvalidDisputeRules {});
+    }
+    Ok(())
+}
+
+// platform_fee is expressed in basis points
+fn validate_platform_fee(fee: Uint128) -> Result<(), ContractError> {
+    if fee > Uint128::new(10000) {
+        return Err(ContractError::InvalidPlatformFee {});
+    }
+    Ok(())
+}
+
+/// Fields of `ExecuteMsg::CreateMarket`, with the defaults applied
+pub struct CreateMarketParams {
+    pub category: String,
+    pub question: String,
+    pub description: String,
+    pub options: Vec<String>,
+    pub start_time: String,
+    pub end_time: String,
+    pub resolution_bond: Uint128,
+    pub resolution_reward: Uint128,
+    pub opening_auction: bool,
+}
+
 pub fn create_market(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    category: String,
-    question: String, 
-    description: String,
-    options: Vec<String>,
-    start_time: String,
-    end_time: String,
-    resolution_bond: Uint128,
-    resolution_reward: Uint128,
+    params: CreateMarketParams,
 ) -> Result<Response, ContractError> {
+    let CreateMarketParams {
+        category,
+        question,
+        description,
+        options,
+        start_time,
+        end_time,
+        resolution_bond,
+        resolution_reward,
+        opening_auction,
+    } = params;
     let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::MarketCreation)?;
 
     // Fix Bug ID #11: Validate resolution_bond
     if resolution_bond.is_zero() || resolution_bond < config.min_bet {
@@ -206,6 +649,7 @@ pub fn create_market(
         creator: info.sender.clone(),
         question,
         description,
+        option_statuses: vec![OptionStatus::Active; options.len()],
         options,
         category,
         start_time,
@@ -214,6 +658,8 @@ pub fn create_market(
         resolution_bond,
         resolution_reward,
         result: None,
+        opening_auction,
+        platform_fee: config.platform_fee,
     };
 
     MARKETS.save(deps.storage, market_id, &market)?;
@@ -257,746 +703,1934 @@ pub fn cancel_market(
     let config = CONFIG.load(deps.storage)?;
     let mut market = MARKETS.load(deps.storage, market_id)?;
 
-    // Only admin or market creator can cancel the market
-    if info.sender != config.admin && info.sender != market.creator {
+    // Only operators or the market creator can cancel the market
+    if !has_role(deps.storage, &config, &info.sender, Role::Operator) && info.sender != market.creator {
         return Err(ContractError::Unauthorized {});
     }
 
-    // Can only cancel active markets
-    if market.status != MarketStatus::Active {
+    // Can only cancel active or suspended markets
+    if market.status != MarketStatus::Active && market.status != MarketStatus::Suspended {
         return Err(ContractError::InvalidMarketState {});
     }
 
     market.status = MarketStatus::Canceled;
     MARKETS.save(deps.storage, market_id, &market)?;
 
-    //Implement logic to refund all bets
-    let refund_messages = refund_all_bets(&mut deps, market_id)?;
+    // Refund all bets to the traders' balances
+    refund_all_bets(&mut deps, market_id)?;
 
     Ok(Response::new()
-        .add_messages(refund_messages)
         .add_attribute("method", "cancel_market")
         .add_attribute("market_id", market_id.to_string()))
 }
 
-fn refund_all_bets(deps: &mut DepsMut, market_id: u64) -> Result<Vec<CosmosMsg>, ContractError> {
+pub fn open_market(
+    deps: DepsMut,
+    env: Env,
+    market_id: u64,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let mut refund_messages = Vec::new();
-
-    // Refund open and partially filled orders
-    let orders: Vec<Order> = ORDERS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|r| {
-            let order = r.unwrap().1;
-            if order.market_id == market_id && 
-               (order.status == OrderStatus::Open || order.status == OrderStatus::PartiallyFilled) {
-                Some(order)
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    for mut order in orders {
-        let refund_amount = match order.side {
-            OrderSide::Back => order.amount - order.filled_amount,
-            OrderSide::Lay => (order.amount - order.filled_amount).multiply_ratio(order.odds - 100, 100u128),
-        };
-
-        if refund_amount > Uint128::zero() {
-            let refund_msg = CosmosMsg::Bank(BankMsg::Send {
-                to_address: order.creator.to_string(),
-                amount: vec![Coin {
-                    denom: config.token_denom.clone(),
-                    amount: refund_amount,
-                }],
-            });
-            refund_messages.push(refund_msg);
+    ensure_not_paused(&config, PauseScope::Trading)?;
+    let mut market = MARKETS.load(deps.storage, market_id)?;
 
-            // Update order status
-            order.status = OrderStatus::Canceled;
-            ORDERS.save(deps.storage, order.id, &order)?;
-        }
+    // Only markets with a pending opening auction need to be opened
+    if market.status != MarketStatus::Active || !market.opening_auction {
+        return Err(ContractError::InvalidMarketState {});
     }
 
-    // Refund matched bets
-    let matched_bets: Vec<MatchedBet> = MATCHED_BETS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|r| {
-            let matched_bet = r.unwrap().1;
-            if matched_bet.market_id == market_id && !matched_bet.redeemed {
-                Some(matched_bet)
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    for mut matched_bet in matched_bets {
-        // Refund back user
-        let back_refund_msg = CosmosMsg::Bank(BankMsg::Send {
-            to_address: matched_bet.back_user.to_string(),
-            amount: vec![Coin {
-                denom: config.token_denom.clone(),
-                amount: matched_bet.amount,
-            }],
-        });
-        refund_messages.push(back_refund_msg);
+    // Check if the market start time has passed
+    if env.block.time.seconds() < market.start_time {
+        return Err(ContractError::MarketNotOpen {});
+    }
 
-        // Refund lay user
-        let lay_amount = matched_bet.amount.multiply_ratio(matched_bet.odds - 100, 100u128);
-        let lay_refund_msg = CosmosMsg::Bank(BankMsg::Send {
-            to_address: matched_bet.lay_user.to_string(),
-            amount: vec![Coin {
-                denom: config.token_denom.clone(),
-                amount: lay_amount,
-            }],
-        });
-        refund_messages.push(lay_refund_msg);
+    let results = run_opening_auction(deps.storage, &env, &mut market)?;
 
-        // Mark matched bet as redeemed
-        matched_bet.redeemed = true;
-        MATCHED_BETS.save(deps.storage, matched_bet.id, &matched_bet)?;
+    let mut response = Response::new()
+        .add_attribute("method", "open_market")
+        .add_attribute("market_id", market_id.to_string());
+    for (option_id, odds, volume) in results {
+        response = response.add_attribute("auction", format!("{}:{}:{}", option_id, odds, volume));
     }
-
-    Ok(refund_messages)
+    Ok(response)
 }
 
-pub fn close_market(
-    mut deps: DepsMut,
-    env: Env,
+pub fn suspend_market(
+    deps: DepsMut,
     info: MessageInfo,
     market_id: u64,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let mut market = MARKETS.load(deps.storage, market_id)?;
 
-    // Only admin can close the market
-    if info.sender != config.admin {
+    // Only operators or the market creator can suspend the market
+    if !has_role(deps.storage, &config, &info.sender, Role::Operator) && info.sender != market.creator {
         return Err(ContractError::Unauthorized {});
     }
 
-    // Can only close active markets
+    // Can only suspend active markets
     if market.status != MarketStatus::Active {
-        return Err(ContractError::InvalidMarketState {});
-    }
-
-    // Check if the market end time has passed
-    if env.block.time.seconds() < market.end_time {
-        return Err(ContractError::MarketNotEnded {});
+        return Err(ContractError::MarketNotActive {});
     }
 
-    market.status = MarketStatus::Closed;
+    market.status = MarketStatus::Suspended;
     MARKETS.save(deps.storage, market_id, &market)?;
 
-     // Refund unmatched orders
-     let refund_messages = refund_unmatched_orders(&mut deps, market_id)?;
+    // Cancel and refund every resting order that did not ask to be kept through a suspension
+    let mut canceled_orders = 0u64;
+    for mut order in resting_market_orders(deps.storage, market_id)? {
+        if !order.keep_on_suspend {
+            refund_order(deps.storage, &mut order)?;
+            canceled_orders += 1;
+        }
+    }
 
     Ok(Response::new()
-        .add_messages(refund_messages)
-        .add_attribute("method", "close_market")
-        .add_attribute("market_id", market_id.to_string()))
+        .add_attribute("method", "suspend_market")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("canceled_orders", canceled_orders.to_string()))
 }
 
-pub fn refund_unmatched_orders(
-    deps: &mut DepsMut,
+pub fn resume_market(
+    deps: DepsMut,
+    info: MessageInfo,
     market_id: u64,
-) -> Result<Vec<CosmosMsg>, ContractError> {
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let mut refund_messages = Vec::new();
+    let mut market = MARKETS.load(deps.storage, market_id)?;
 
-    let orders: Vec<Order> = ORDERS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|r| {
-            let order = r.unwrap().1;
-            if order.market_id == market_id && 
-               (order.status == OrderStatus::Open || order.status == OrderStatus::PartiallyFilled) {
-                Some(order)
-            } else {
-                None
-            }
-        })
-        .collect();
+    // Only operators or the market creator can resume the market
+    if !has_role(deps.storage, &config, &info.sender, Role::Operator) && info.sender != market.creator {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    for mut order in orders {
-        let refund_amount = match order.side {
-            OrderSide::Back => order.amount - order.filled_amount,
-            OrderSide::Lay => (order.amount - order.filled_amount).multiply_ratio(order.odds - 100, 100u128),
-        };
+    // Can only resume suspended markets
+    if market.status != MarketStatus::Suspended {
+        return Err(ContractError::MarketNotSuspended {});
+    }
 
-        if refund_amount > Uint128::zero() {
-            let refund_msg = CosmosMsg::Bank(BankMsg::Send {
-                to_address: order.creator.to_string(),
-                amount: vec![Coin {
-                    denom: config.token_denom.clone(),
-                    amount: refund_amount,
-                }],
-            });
-            refund_messages.push(refund_msg);
+    market.status = MarketStatus::Active;
+    MARKETS.save(deps.storage, market_id, &market)?;
 
-            // Update order status
-            order.status = OrderStatus::Canceled;
-            order.amount = order.filled_amount;
-            ORDERS.save(deps.storage, order.id, &order)?;
-        }
+    Ok(Response::new()
+        .add_attribute("method", "resume_market")
+        .add_attribute("market_id", market_id.to_string()))
+}
+
+pub fn suspend_option(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: u64,
+    option_id: u8,
+) -> Result<Response, ContractError> {
+    let mut market = load_option_for_update(deps.as_ref(), &info, market_id, option_id)?;
+
+    // Only active options can be suspended
+    if market.option_statuses[option_id as usize] != OptionStatus::Active {
+        return Err(ContractError::OptionNotActive {});
     }
 
-    Ok(refund_messages)
+    // Resting orders stay on the book but no new order can match them
+    market.option_statuses[option_id as usize] = OptionStatus::Suspended;
+    MARKETS.save(deps.storage, market_id, &market)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "suspend_option")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("option_id", option_id.to_string()))
 }
 
-pub fn place_order(
-    mut deps: DepsMut,
+pub fn resume_option(
+    deps: DepsMut,
     env: Env,
     info: MessageInfo,
     market_id: u64,
     option_id: u8,
-    _order_type: OrderType,
-    side: OrderSide,
-    amount: Uint128,
-    odds: u32,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    let mut market = MARKETS.load(deps.storage, market_id)?;
-
-    // Check if market is active
-    if market.status != MarketStatus::Active {
-        return Err(ContractError::MarketNotActive {});
-    }
+    let mut market = load_option_for_update(deps.as_ref(), &info, market_id, option_id)?;
 
-    // Check if market has ended
-    if env.block.time.seconds() > market.end_time {
-        market.status = MarketStatus::Closed;
-        MARKETS.save(deps.storage, market_id, &market)?;
-        // Fix Bug ID #6: Return Ok instead of Err to avoid transaction revert
-        return Ok(Response::new()
-            .add_attribute("action", "market_closed")
-            .add_attribute("market_id", market_id.to_string())
-            .add_attribute("message", "Market is closed, no more orders can be placed"));
+    // Only suspended options can be resumed
+    if market.option_statuses[option_id as usize] != OptionStatus::Suspended {
+        return Err(ContractError::OptionNotSuspended {});
     }
 
-    // Check if the bet amount is above the minimum
-    if amount < config.min_bet {
-        println!("amount: {:?}", amount);
-        println!("config.min_bet: {:?}", config.min_bet);
-        return Err(ContractError::BetTooSmall {});
-    }
+    market.option_statuses[option_id as usize] = OptionStatus::Active;
+    MARKETS.save(deps.storage, market_id, &market)?;
 
-    // Fix Bug ID #20: Use Rust's range feature for more idiomatic validation
-    if !(100..=9900).contains(&odds) {
-        return Err(ContractError::InvalidOdds {});
-    }
+    let mut response = Response::new()
+        .add_attribute("method", "resume_option")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("option_id", option_id.to_string());
 
-    // Calculate required amount
-    let required_amount = match side {
-        OrderSide::Back => amount,
-        OrderSide::Lay => {
-            // Fix Bug ID #1: Ensure required_amount is never zero for Lay orders
-            let lay_amount = amount.multiply_ratio(odds - 100, 100u128);
-            if lay_amount.is_zero() {
-                return Err(ContractError::InvalidOdds {});
-            }
-            lay_amount
+    // Orders queued while the option missed the opening auction are uncrossed now
+    if market.status == MarketStatus::Active && !market.opening_auction {
+        if let Some((odds, volume)) = uncross_option(deps.storage, env.block.time.seconds(), &market, option_id)? {
+            response = response.add_attribute("auction", format!("{}:{}:{}", option_id, odds, volume));
         }
-    };
-
-    // Check if sufficient funds are sent
-    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom)
-        .ok_or(ContractError::NoFundsSent {})?;
-    if sent_funds.amount < required_amount {
-        return Err(ContractError::InsufficientFunds {});
     }
 
-    // Create new order
-    let order_id = ORDER_COUNT.load(deps.storage)? + 1;
-    let order = Order {
-        id: order_id,
-        market_id,
-        creator: info.sender.clone(),
-        option_id,
-        side,
-        amount,
-        odds,
-        filled_amount: Uint128::zero(),
-        status: OrderStatus::Open,
-        timestamp: env.block.time.seconds(),
-    };
+    Ok(response)
+}
 
-    // Save the order
-    ORDERS.save(deps.storage, order_id, &order)?;
-    ORDER_COUNT.save(deps.storage, &order_id)?;
+pub fn void_option(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: u64,
+    option_id: u8,
+) -> Result<Response, ContractError> {
+    let mut market = load_option_for_update(deps.as_ref(), &info, market_id, option_id)?;
 
-    // Match the order
-    let (matched_amount, matched_bets) = match_orders(&mut deps, &env, &order)?;
+    if market.option_statuses[option_id as usize] == OptionStatus::Void {
+        return Err(ContractError::OptionNotActive {});
+    }
 
-    // Update order status based on matching result
-    let mut updated_order = ORDERS.load(deps.storage, order_id)?;
-    if matched_amount == amount {
-        updated_order.status = OrderStatus::Filled;
-        updated_order.amount = matched_amount;  // Set amount to matched amount
-    } else if matched_amount > Uint128::zero() {
-        updated_order.status = OrderStatus::PartiallyFilled;
+    // An option can only be voided before the market has a proposed result
+    if PROPOSALS.has(deps.storage, market_id) {
+        return Err(ContractError::InvalidMarketState {});
     }
-    updated_order.filled_amount = matched_amount;
-    ORDERS.save(deps.storage, order_id, &updated_order)?;
 
-    // If there's any excess funds, return them
-    let excess_funds = sent_funds.amount - required_amount;
-    let mut response = Response::new()
-        .add_attribute("method", "place_order")
-        .add_attribute("order_id", order_id.to_string())
-        .add_attribute("matched_amount", matched_amount.to_string())
-        .add_attribute("remaining_matched_bets", matched_bets.len().to_string());
+    market.option_statuses[option_id as usize] = OptionStatus::Void;
+    MARKETS.save(deps.storage, market_id, &market)?;
 
-    if excess_funds > Uint128::zero() {
-        let refund_msg = BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![Coin {
-                denom: config.token_denom,
-                amount: excess_funds,
-            }],
-        };
-        response = response.add_message(refund_msg);
+    // Cancel the option's resting orders
+    for mut order in resting_option_orders(deps.storage, market_id, option_id)? {
+        refund_order(deps.storage, &mut order)?;
     }
 
-    Ok(response)
+    // Refund the option's matched bets, the rest of the market settles normally
+    let matched_bets: Vec<MatchedBet> = MATCHED_BETS
+        .idx
+        .market
+        .prefix(market_id)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .filter(|r| !matches!(r, Ok((_, matched_bet)) if matched_bet.option_id != option_id || matched_bet.redeemed))
+        .map(|r| r.map(|(_, matched_bet)| matched_bet))
+        .collect::<StdResult<Vec<MatchedBet>>>()?;
+    let refunded_bets = matched_bets.len();
+    for mut matched_bet in matched_bets {
+        refund_matched_bet(deps.storage, &mut matched_bet)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "void_option")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("option_id", option_id.to_string())
+        .add_attribute("refunded_bets", refunded_bets.to_string()))
 }
 
-pub fn cancel_order(
-    deps: DepsMut,
-    info: MessageInfo,
-    order_id: u64,
-) -> Result<Response, ContractError> {
+/// Loads a market whose option is about to change status, checking the sender and market state
+fn load_option_for_update(deps: Deps, info: &MessageInfo, market_id: u64, option_id: u8) -> Result<Market, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let mut order = ORDERS.load(deps.storage, order_id)?;
+    let market = MARKETS.load(deps.storage, market_id)?;
 
-    // Check if the order belongs to the sender
-    if order.creator != info.sender {
+    // Only operators or the market creator can change the status of an option
+    if !has_role(deps.storage, &config, &info.sender, Role::Operator) && info.sender != market.creator {
         return Err(ContractError::Unauthorized {});
     }
 
-    // Check if the order can be cancelled
-    if order.status != OrderStatus::Open && order.status != OrderStatus::PartiallyFilled {
-        return Err(ContractError::OrderNotCancellable {});
+    // Options of settled markets can no longer change
+    if !matches!(market.status, MarketStatus::Active | MarketStatus::Suspended | MarketStatus::Closed) {
+        return Err(ContractError::InvalidMarketState {});
     }
 
-    // Calculate refund amount
-    let refund_amount = match order.side {
-        OrderSide::Back => order.amount - order.filled_amount,
-        OrderSide::Lay => (order.amount - order.filled_amount).multiply_ratio(order.odds - 100, 100u128),
-    };
-
-    // Update order status
-    order.status = OrderStatus::Canceled;
-    order.amount = order.filled_amount;  // Set the amount to the filled amount
-    ORDERS.save(deps.storage, order_id, &order)?;
-
-    // Prepare refund message
-    let refund_msg = BankMsg::Send {
-        to_address: order.creator.to_string(),
-        amount: vec![Coin {
-            denom: config.token_denom,
-            amount: refund_amount,
-        }],
-    };
+    if option_id as usize >= market.options.len() {
+        return Err(ContractError::InvalidOption {});
+    }
 
-    Ok(Response::new()
-        .add_message(refund_msg)
-        .add_attribute("method", "cancel_order")
-        .add_attribute("order_id", order_id.to_string())
-        .add_attribute("refund_amount", refund_amount.to_string()))
+    Ok(market)
 }
 
-pub fn match_orders(deps: &mut DepsMut, env: &Env, new_order: &Order) -> Result<(Uint128, Vec<MatchedBet>), ContractError> {
-    let mut matched_amount = Uint128::zero();
-    let mut matched_bets = Vec::new();
-    let opposite_side = if new_order.side == OrderSide::Back { OrderSide::Lay } else { OrderSide::Back };
-    let mut orders: Vec<Order> = ORDERS
+fn refund_all_bets(deps: &mut DepsMut, market_id: u64) -> Result<(), ContractError> {
+    // Refund open and partially filled orders
+    refund_unmatched_orders(deps, market_id)?;
+
+    // Refund matched bets
+    let matched_bets: Vec<MatchedBet> = MATCHED_BETS
+        .idx
+        .market
+        .prefix(market_id)
         .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
         .filter_map(|r| {
-            let order = r.unwrap().1;
-            if order.market_id == new_order.market_id && 
-               order.option_id == new_order.option_id && 
-               order.side == opposite_side &&
-               (order.status == OrderStatus::Open || order.status == OrderStatus::PartiallyFilled) &&
-               order.amount > order.filled_amount {
-                Some(order)
+            let matched_bet = r.unwrap().1;
+            if !matched_bet.redeemed {
+                Some(matched_bet)
             } else {
                 None
             }
         })
         .collect();
 
-    // Sort orders based on the new order's side
-    orders.sort_by(|a, b| {
-        match new_order.side {
-            OrderSide::Back => a.odds.cmp(&b.odds).then_with(|| a.timestamp.cmp(&b.timestamp)),
-            OrderSide::Lay => b.odds.cmp(&a.odds).then_with(|| a.timestamp.cmp(&b.timestamp)),
-        }
-    });
-
-
-    for order in &mut orders {
-        if matched_amount == new_order.amount {
-            break;
-        }
-
-        // Check if the odds are favorable for matching
-        let odds_match = match new_order.side {
-            OrderSide::Back => new_order.odds <= order.odds,
-            OrderSide::Lay => new_order.odds >= order.odds,
-        };
-
-        if !odds_match {
-            continue;
-        }
-
-        let available_amount = order.amount - order.filled_amount;
-        let match_amount = std::cmp::min(new_order.amount - matched_amount, available_amount);
-
-        let matched_bet_id = MATCHED_BET_COUNT.load(deps.storage)? + 1;
-        let matched_bet = MatchedBet {
-            id: matched_bet_id,
-            market_id: new_order.market_id,
-            option_id: new_order.option_id,
-            amount: match_amount,
-            odds: order.odds,
-            timestamp: env.block.time.seconds(),
-            back_user: if new_order.side == OrderSide::Back { new_order.creator.clone() } else { order.creator.clone() },
-            lay_user: if new_order.side == OrderSide::Lay { new_order.creator.clone() } else { order.creator.clone() },
-            redeemed: false,
-        };
-
-        MATCHED_BETS.save(deps.storage, matched_bet_id, &matched_bet)?;
-        MATCHED_BET_COUNT.save(deps.storage, &matched_bet_id)?;
-        matched_bets.push(matched_bet);
+    for mut matched_bet in matched_bets {
+        refund_matched_bet(deps.storage, &mut matched_bet)?;
+    }
 
-        matched_amount += match_amount;
-        order.filled_amount += match_amount;
+    Ok(())
+}
 
-        if order.filled_amount == order.amount {
-            order.status = OrderStatus::Filled;
-        } else {
-            order.status = OrderStatus::PartiallyFilled;
-        }
+/// Returns the stake and liability of a matched bet to both sides and marks it redeemed
+fn refund_matched_bet(storage: &mut dyn Storage, matched_bet: &mut MatchedBet) -> StdResult<()> {
+    // Refund back user, less collateral already released on their position
+    let back_amount = reclaim_released(storage, &matched_bet.back_user, matched_bet.market_id, matched_bet.option_id, matched_bet.amount)?;
+    credit_balance(storage, &matched_bet.back_user, back_amount)?;
 
-        // Fix Bug ID #21: Remove unnecessary reference creation
-        ORDERS.save(deps.storage, order.id, order)?;
-    }
+    // Refund lay user
+    let lay_amount = matched_bet.amount.multiply_ratio(matched_bet.odds - 100, 100u128);
+    let lay_amount = reclaim_released(storage, &matched_bet.lay_user, matched_bet.market_id, matched_bet.option_id, lay_amount)?;
+    credit_balance(storage, &matched_bet.lay_user, lay_amount)?;
 
-    let mut updated_new_order = new_order.clone();
-    updated_new_order.filled_amount = matched_amount;
-    if matched_amount == new_order.amount {
-        updated_new_order.status = OrderStatus::Filled;
-    } else if matched_amount > Uint128::zero() {
-        updated_new_order.status = OrderStatus::PartiallyFilled;
-    }
-    ORDERS.save(deps.storage, new_order.id, &updated_new_order)?;
+    // Mark matched bet as redeemed
+    matched_bet.redeemed = true;
+    MATCHED_BETS.save(storage, matched_bet.id, matched_bet)?;
 
-    Ok((matched_amount, matched_bets))
+    Ok(())
 }
 
-pub fn redeem_winnings(
-    deps: DepsMut,
-    _env: Env,
+pub fn close_market(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    matched_bet_id: u64,
+    market_id: u64,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let mut matched_bet = MATCHED_BETS.load(deps.storage, matched_bet_id)?;
-    let market = MARKETS.load(deps.storage, matched_bet.market_id)?;
+    let mut market = MARKETS.load(deps.storage, market_id)?;
 
-    // Check if market is resolved
-    if market.status != MarketStatus::Resolved {
-        return Err(ContractError::MarketNotResolved {});
+    // Only operators can close the market
+    if !has_role(deps.storage, &config, &info.sender, Role::Operator) {
+        return Err(ContractError::Unauthorized {});
     }
 
-    // Check if bet is already redeemed
-    if matched_bet.redeemed {
-        return Err(ContractError::AlreadyRedeemed {});
+    // Can only close active or suspended markets
+    if market.status != MarketStatus::Active && market.status != MarketStatus::Suspended {
+        return Err(ContractError::InvalidMarketState {});
     }
 
-    // Check if the caller is the winner
-    let is_winner = matched_bet.option_id == market.result.unwrap();
-    if (is_winner && matched_bet.back_user != info.sender) || (!is_winner && matched_bet.lay_user != info.sender) {
-        return Err(ContractError::Unauthorized {});
+    // Check if the market end time has passed
+    if env.block.time.seconds() < market.end_time {
+        return Err(ContractError::MarketNotEnded {});
     }
 
-    // Calculate winnings
-    let winnings = if is_winner {
-        matched_bet.amount.multiply_ratio(matched_bet.odds, 100u128)
-    } else {
-        matched_bet.amount
-    };
-
-    // Mark bet as redeemed
-    matched_bet.redeemed = true;
-    MATCHED_BETS.save(deps.storage, matched_bet_id, &matched_bet)?;
+    market.status = MarketStatus::Closed;
+    MARKETS.save(deps.storage, market_id, &market)?;
 
-    // Send winnings
-    let send_msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![Coin {
-            denom: config.token_denom,
-            amount: winnings,
-        }],
-    };
+    // Refund unmatched orders to the traders' balances
+    refund_unmatched_orders(&mut deps, market_id)?;
 
     Ok(Response::new()
-        .add_message(send_msg)
-        .add_attribute("method", "redeem_winnings")
-        .add_attribute("matched_bet_id", matched_bet_id.to_string())
-        .add_attribute("winnings", winnings.to_string()))
+        .add_attribute("method", "close_market")
+        .add_attribute("market_id", market_id.to_string()))
 }
 
+pub fn refund_unmatched_orders(
+    deps: &mut DepsMut,
+    market_id: u64,
+) -> Result<(), ContractError> {
+    let orders = resting_market_orders(deps.storage, market_id)?;
 
-pub fn add_to_whitelist(
-    deps: DepsMut,
-    info: MessageInfo,
-    address: Addr,
-) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-
-    // Only admin can add to whitelist
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    for mut order in orders {
+        refund_order(deps.storage, &mut order)?;
     }
 
-    // Fix Bug ID #9: Validate and normalize the address
-    let validated_address = deps.api.addr_validate(&address.to_string().to_lowercase())?;
+    Ok(())
+}
 
-    // Check if the address is already whitelisted
-    if WHITELISTED_ADDRESSES.may_load(deps.storage, validated_address.clone())?.is_some() {
-        return Err(ContractError::AlreadyWhitelisted {});
+/// Cancels a resting order and returns the collateral of its unfilled part to the creator
+fn refund_order(storage: &mut dyn Storage, order: &mut Order) -> StdResult<()> {
+    let refund_amount = match order.side {
+        OrderSide::Back => order.amount - order.filled_amount,
+        OrderSide::Lay => (order.amount - order.filled_amount).multiply_ratio(order.odds - 100, 100u128),
+    };
+
+    if refund_amount > Uint128::zero() {
+        credit_balance(storage, &order.creator, refund_amount)?;
+
+        // Update order status
+        order.status = OrderStatus::Canceled;
+        order.amount = order.filled_amount;
+        save_order(storage, order)?;
     }
 
-    WHITELISTED_ADDRESSES.save(deps.storage, validated_address.clone(), &true)?;
+    Ok(())
+}
 
-    Ok(Response::new()
-        .add_attribute("method", "add_to_whitelist")
-        .add_attribute("address", validated_address.to_string()))
+fn credit_balance(storage: &mut dyn Storage, user: &Addr, amount: Uint128) -> StdResult<Uint128> {
+    BALANCES.update(storage, user.clone(), |balance| -> StdResult<Uint128> {
+        Ok(balance.unwrap_or_default() + amount)
+    })
 }
 
-pub fn remove_from_whitelist(
+fn debit_balance(storage: &mut dyn Storage, user: &Addr, amount: Uint128) -> Result<Uint128, ContractError> {
+    let balance = BALANCES.may_load(storage, user.clone())?.unwrap_or_default();
+    if balance < amount {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    BALANCES.save(storage, user.clone(), &(balance - amount))?;
+    Ok(balance - amount)
+}
+
+pub fn stake(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    address: Addr,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
-    // Only admin can remove from whitelist
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom)
+        .ok_or(ContractError::NoFundsSent {})?;
+    if sent_funds.amount.is_zero() {
+        return Err(ContractError::NoFundsSent {});
     }
-     // Fix Bug ID #9: Validate and normalize the address
-    let validated_address = deps.api.addr_validate(&address.to_string().to_lowercase())?;
 
-    WHITELISTED_ADDRESSES.remove(deps.storage, validated_address.clone());
+    let staked = STAKES.update(deps.storage, info.sender.clone(), env.block.height, |stake| -> StdResult<Uint128> {
+        Ok(stake.unwrap_or_default() + sent_funds.amount)
+    })?;
+    TOTAL_STAKED.update(deps.storage, |total| -> StdResult<Uint128> { Ok(total + sent_funds.amount) })?;
 
     Ok(Response::new()
-        .add_attribute("method", "remove_from_whitelist")
-        .add_attribute("address", validated_address.to_string()))
+        .add_attribute("method", "stake")
+        .add_attribute("staker", info.sender.to_string())
+        .add_attribute("amount", sent_funds.amount.to_string())
+        .add_attribute("staked", staked.to_string()))
 }
 
-pub fn propose_market_result(
+pub fn unstake(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    market_id: u64,
-    proposed_result: u8,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let mut market = MARKETS.load(deps.storage, market_id)?;
-
-    // Fix Bug ID #5: Ensure market is already properly closed
-    if market.status != MarketStatus::Closed {
-        return Err(ContractError::InvalidMarketState {});
-    }
-
-    // Fix Bug ID #3: Check if market is canceled
-    if market.status == MarketStatus::Canceled {
-        return Err(ContractError::InvalidMarketState {});
-    }
-
-    // Check if the market end time has passed
-    if env.block.time.seconds() <= market.end_time {
-        return Err(ContractError::MarketNotEnded {});
-    }
 
-    // Fix Bug ID #4: Check if any proposal exists regardless of status
-    if PROPOSALS.may_load(deps.storage, market_id)?.is_some() {
-        return Err(ContractError::ProposalAlreadyExists {});
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
     }
 
-    // Check if the correct bond amount is sent
-    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom);
-    if sent_funds.is_none() || sent_funds.unwrap().amount != market.resolution_bond {
-        return Err(ContractError::IncorrectBondAmount {});
+    let stake = STAKES.may_load(deps.storage, info.sender.clone())?.unwrap_or_default();
+    if stake < amount {
+        return Err(ContractError::InsufficientFunds {});
     }
-
-    // Create and save proposal
-    let proposal = ResolutionProposal {
-        market_id,
-        proposer: info.sender.clone(),
-        proposed_result,
-        bond_amount: market.resolution_bond,
-        proposal_time: env.block.time.seconds(),
-        challenge_deadline: env.block.time.seconds() + config.challenging_period,
-        status: ProposalStatus::Active,
-    };
-    PROPOSALS.save(deps.storage, market_id, &proposal)?;
-
-    // Update market status
-    market.status = MarketStatus::Closed;
-    MARKETS.save(deps.storage, market_id, &market)?;
+    STAKES.save(deps.storage, info.sender.clone(), &(stake - amount), env.block.height)?;
+    TOTAL_STAKED.update(deps.storage, |total| -> StdResult<Uint128> { Ok(total - amount) })?;
+
+    let release_at = env.block.time.seconds() + config.staking.unbonding_period;
+    UNBONDING.update(deps.storage, info.sender.clone(), |unbonding| -> StdResult<Vec<Unbonding>> {
+        let mut unbonding = unbonding.unwrap_or_default();
+        unbonding.push(Unbonding { amount, release_at });
+        Ok(unbonding)
+    })?;
 
     Ok(Response::new()
-        .add_attribute("method", "propose_market_result")
-        .add_attribute("market_id", market_id.to_string())
-        .add_attribute("proposed_result", proposed_result.to_string())
-        .add_attribute("proposer", info.sender.to_string())
-        .add_attribute("bond_amount", market.resolution_bond.to_string()))
+        .add_attribute("method", "unstake")
+        .add_attribute("staker", info.sender.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("release_at", release_at.to_string()))
 }
 
-pub fn raise_dispute(
+pub fn claim_unstaked(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    market_id: u64,
-    proposed_outcome: u8,
-    evidence: String,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let mut market = MARKETS.load(deps.storage, market_id)?;
-    let mut proposal = PROPOSALS.load(deps.storage, market_id)?;
+    let now = env.block.time.seconds();
 
-    // Check if proposal is active
-    if proposal.status != ProposalStatus::Active {
-        return Err(ContractError::InvalidProposalState {});
+    let unbonding = UNBONDING.may_load(deps.storage, info.sender.clone())?.unwrap_or_default();
+    let (released, pending): (Vec<Unbonding>, Vec<Unbonding>) = unbonding.into_iter().partition(|entry| entry.release_at <= now);
+    let amount: Uint128 = released.iter().map(|entry| entry.amount).sum();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
     }
 
-    // Check if within challenging period
-    if env.block.time.seconds() > proposal.challenge_deadline {
-        return Err(ContractError::ChallengePeriodEnded {});
+    if pending.is_empty() {
+        UNBONDING.remove(deps.storage, info.sender.clone());
+    } else {
+        UNBONDING.save(deps.storage, info.sender.clone(), &pending)?;
     }
 
-    // Check if the correct bond amount is sent
-    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom);
-    if sent_funds.is_none() || sent_funds.unwrap().amount != market.resolution_bond {
-        return Err(ContractError::IncorrectBondAmount {});
-    }
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.token_denom,
+                amount,
+            }],
+        }))
+        .add_attribute("method", "claim_unstaked")
+        .add_attribute("staker", info.sender.to_string())
+        .add_attribute("amount", amount.to_string()))
+}
 
-    // Create and save dispute
-    let dispute = Dispute {
-        market_id,
-        challenger: info.sender.clone(),
-        proposed_outcome,
-        evidence,
-        status: DisputeStatus::Active,
-        created_at: env.block.time.seconds(),
-    };
-    DISPUTES.save(deps.storage, market_id, &dispute)?;
+pub fn deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-    // Update proposal status
-    proposal.status = ProposalStatus::Challenged;
-    PROPOSALS.save(deps.storage, market_id, &proposal)?;
+    ensure_token_denom(&info, &config)?;
+    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom)
+        .ok_or(ContractError::NoFundsSent {})?;
+    if sent_funds.amount.is_zero() {
+        return Err(ContractError::NoFundsSent {});
+    }
 
-    market.status = MarketStatus::InDispute;
-    MARKETS.save(deps.storage, market_id, &market)?;
+    let balance = credit_balance(deps.storage, &info.sender, sent_funds.amount)?;
 
     Ok(Response::new()
-        .add_attribute("method", "raise_dispute")
-        .add_attribute("market_id", market_id.to_string())
-        .add_attribute("proposed_outcome", proposed_outcome.to_string())
-        .add_attribute("challenger", info.sender.to_string())
-        .add_attribute("bond_amount", market.resolution_bond.to_string()))
+        .add_attribute("method", "deposit")
+        .add_attribute("user", info.sender.to_string())
+        .add_attribute("amount", sent_funds.amount.to_string())
+        .add_attribute("balance", balance.to_string()))
 }
 
-pub fn cast_vote(
+/// Rejects coins in any other denom than `token_denom`, the ledger could not account for them
+fn ensure_token_denom(info: &MessageInfo, config: &Config) -> Result<(), ContractError> {
+    if info.funds.iter().any(|coin| coin.denom != config.token_denom) {
+        return Err(ContractError::InvalidFunds {});
+    }
+    Ok(())
+}
+
+pub fn withdraw(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
-    market_id: u64,
-    vote: u8,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
-    // Fix Bug ID #7: Validate vote is either 0 or 1
-    if vote > 1 {
-        return Err(ContractError::InvalidVote {});
-    }
-
     let config = CONFIG.load(deps.storage)?;
-    let market = MARKETS.load(deps.storage, market_id)?;
-    let dispute = DISPUTES.load(deps.storage, market_id)?;
 
-    // Check if voter is whitelisted
-    if !WHITELISTED_ADDRESSES.has(deps.storage, info.sender.clone()) {
-        return Err(ContractError::NotWhitelisted {});
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
     }
 
-    // Check if market is in disputed state
-    if market.status != MarketStatus::InDispute {
-        return Err(ContractError::InvalidMarketState {});
-    }
+    let balance = debit_balance(deps.storage, &info.sender, amount)?;
 
-    // Check if within voting period
-    if env.block.time.seconds() > dispute.created_at + config.voting_period {
-        return Err(ContractError::VotingPeriodEnded {});
-    }
+    let send_msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin {
+            denom: config.token_denom,
+            amount,
+        }],
+    };
 
-    // Check if voter has already voted
-    if VOTES.has(deps.storage, (market_id, info.sender.clone())) {
-        return Err(ContractError::AlreadyVoted {});
+    Ok(Response::new()
+        .add_message(send_msg)
+        .add_attribute("method", "withdraw")
+        .add_attribute("user", info.sender.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("balance", balance.to_string()))
+}
+
+fn book_key(order: &Order) -> OrderBookKey {
+    (order.market_id, (order.option_id, order.side.key()), (order.side.price_rank(order.odds), order.id))
+}
+
+/// Saves the order and keeps ORDER_BOOK in sync: an order rests on the book
+/// exactly while it is open or partially filled.
+fn save_order(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    ORDERS.save(storage, order.id, order)?;
+    if order.status == OrderStatus::Open || order.status == OrderStatus::PartiallyFilled {
+        ORDER_BOOK.save(storage, book_key(order), &Empty {})?;
+    } else {
+        ORDER_BOOK.remove(storage, book_key(order));
     }
+    Ok(())
+}
 
-    // Save vote
-    let vote_record = Vote {
-        voter: info.sender.clone(),
-        option_id: vote,
-    };
-    VOTES.save(deps.storage, (market_id, info.sender.clone()), &vote_record)?;
+/// Loads every order resting on the book of a market, across all options and sides
+fn resting_market_orders(storage: &dyn Storage, market_id: u64) -> StdResult<Vec<Order>> {
+    let market = MARKETS.load(storage, market_id)?;
+    let mut orders = Vec::new();
+    for option_id in 0..market.options.len() as u8 {
+        orders.extend(resting_option_orders(storage, market_id, option_id)?);
+    }
+    Ok(orders)
+}
 
-    // Update vote count
-    VOTE_COUNTS.update(deps.storage, (market_id, vote), |count| -> StdResult<u64> {
-        Ok(count.unwrap_or(0) + 1)
-    })?;
+/// Loads the orders resting on both sides of the book of a market option
+fn resting_option_orders(storage: &dyn Storage, market_id: u64, option_id: u8) -> StdResult<Vec<Order>> {
+    let mut orders = Vec::new();
+    for side in [OrderSide::Back, OrderSide::Lay] {
+        for key in ORDER_BOOK
+            .prefix((market_id, (option_id, side.key())))
+            .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+        {
+            let (_, order_id) = key?;
+            orders.push(ORDERS.load(storage, order_id)?);
+        }
+    }
+    Ok(orders)
+}
 
-    Ok(Response::new()
-        .add_attribute("method", "cast_vote")
-        .add_attribute("market_id", market_id.to_string())
-        .add_attribute("voter", info.sender)
-        .add_attribute("vote", vote.to_string()))
+/// Fields of `ExecuteMsg::PlaceOrder`, with the defaults applied
+pub struct OrderParams {
+    pub market_id: u64,
+    pub option_id: u8,
+    pub order_type: OrderType,
+    pub side: OrderSide,
+    pub amount: Uint128,
+    pub odds: u32,
+    pub time_in_force: Option<TimeInForce>,
+    pub keep_on_suspend: bool,
 }
 
-pub fn resolve_dispute(
-    deps: DepsMut,
+pub fn place_order(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    market_id: u64,
+    params: OrderParams,
 ) -> Result<Response, ContractError> {
+    let OrderParams {
+        market_id,
+        option_id,
+        order_type,
+        side,
+        amount,
+        odds,
+        time_in_force,
+        keep_on_suspend,
+    } = params;
     let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Trading)?;
     let mut market = MARKETS.load(deps.storage, market_id)?;
-    let mut proposal = PROPOSALS.load(deps.storage, market_id)?;
 
-    // Only admin can resolve disputes
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    // Check if market is active
+    if market.status != MarketStatus::Active {
+        return Err(ContractError::MarketNotActive {});
     }
 
-    let mut messages = vec![];
-    let mut attributes = vec![
-        ("method".to_string(), "resolve_dispute".to_string()),
-        ("market_id".to_string(), market_id.to_string()),
-    ];
-
-    if proposal.status == ProposalStatus::Active {
-        // No dispute case
-        if env.block.time.seconds() <= proposal.challenge_deadline {
-            return Err(ContractError::ChallengePeriodNotEnded {});
-        }
-
-        // Resolve in favor of the proposer
-        market.status = MarketStatus::Resolved;
+    // Check if market has ended
+    if env.block.time.seconds() > market.end_time {
+        market.status = MarketStatus::Closed;
+        MARKETS.save(deps.storage, market_id, &market)?;
+        // Fix Bug ID #6: Return Ok instead of Err to avoid transaction revert
+        return Ok(Response::new()
+            .add_attribute("action", "market_closed")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("message", "Market is closed, no more orders can be placed"));
+    }
+
+    // Check if the option exists and is open for trading
+    match market.option_statuses.get(option_id as usize) {
+        None => return Err(ContractError::InvalidOption {}),
+        Some(OptionStatus::Active) => {}
+        Some(_) => return Err(ContractError::OptionNotActive {}),
+    }
+
+    // Check if the bet amount is above the minimum
+    if amount < config.min_bet {
+        return Err(ContractError::BetTooSmall {});
+    }
+
+    // Market orders match at whatever the book offers, bounded by the worst acceptable odds
+    let is_market_order = matches!(order_type, OrderType::Market { .. });
+    let odds = match order_type {
+        OrderType::Limit => odds,
+        OrderType::Market { worst_odds: Some(worst_odds) } => worst_odds,
+        OrderType::Market { worst_odds: None } => match side {
+            OrderSide::Back => 100,
+            OrderSide::Lay => 9900,
+        },
+    };
+
+    // Fix Bug ID #20: Use Rust's range feature for more idiomatic validation
+    if !(100..=9900).contains(&odds) {
+        return Err(ContractError::InvalidOdds {});
+    }
+
+    // Validate the time in force, market orders can only be immediate-or-cancel or fill-or-kill
+    let time_in_force = time_in_force.unwrap_or(TimeInForce::GoodTilCancelled);
+    match time_in_force {
+        TimeInForce::PostOnly | TimeInForce::GoodTilDate { .. } if is_market_order => {
+            return Err(ContractError::InvalidTimeInForce {});
+        }
+        TimeInForce::GoodTilDate { expires_at } if expires_at <= env.block.time.seconds() => {
+            return Err(ContractError::InvalidExpiry {});
+        }
+        _ => {}
+    }
+
+    // Orders placed before the market opens queue for its opening auction, if it has one.
+    // The first order after the opening runs the auction before being matched itself.
+    let is_queued = env.block.time.seconds() < market.start_time;
+    if is_queued {
+        if !market.opening_auction {
+            return Err(ContractError::MarketNotOpen {});
+        }
+        if is_market_order || matches!(time_in_force, TimeInForce::FillOrKill | TimeInForce::PostOnly) {
+            return Err(ContractError::InvalidTimeInForce {});
+        }
+    } else if market.opening_auction {
+        run_opening_auction(deps.storage, &env, &mut market)?;
+    }
+
+    // Calculate required amount, market orders are only checked against what they actually match
+    let required_amount = match side {
+        _ if is_market_order => Uint128::zero(),
+        OrderSide::Back => amount,
+        OrderSide::Lay => {
+            // Fix Bug ID #1: Ensure required_amount is never zero for Lay orders
+            let lay_amount = amount.multiply_ratio(odds - 100, 100u128);
+            if lay_amount.is_zero() {
+                return Err(ContractError::InvalidOdds {});
+            }
+            lay_amount
+        }
+    };
+
+    // Funds sent with the order are deposited first, collateral is then locked from the balance
+    ensure_token_denom(&info, &config)?;
+    if let Some(sent_funds) = info.funds.iter().find(|coin| coin.denom == config.token_denom) {
+        credit_balance(deps.storage, &info.sender, sent_funds.amount)?;
+    }
+    let available = BALANCES.may_load(deps.storage, info.sender.clone())?.unwrap_or_default();
+    if available < required_amount {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    // Create new order
+    let order_id = ORDER_COUNT.load(deps.storage)? + 1;
+    let order = Order {
+        id: order_id,
+        market_id,
+        creator: info.sender.clone(),
+        option_id,
+        side: side.clone(),
+        amount,
+        odds,
+        filled_amount: Uint128::zero(),
+        status: OrderStatus::Open,
+        timestamp: env.block.time.seconds(),
+        time_in_force,
+        keep_on_suspend,
+    };
+
+    // Post-only orders must only ever add liquidity
+    if order.time_in_force == TimeInForce::PostOnly && crosses_book(deps.as_ref(), &env, &order)? {
+        return Err(ContractError::PostOnlyWouldMatch {});
+    }
+
+    // Save the order
+    save_order(deps.storage, &order)?;
+    ORDER_COUNT.save(deps.storage, &order_id)?;
+    USER_ORDERS.save(deps.storage, (info.sender.clone(), order_id), &market_id)?;
+
+    // Match the order, queued orders wait for the opening auction
+    let (matched_amount, matched_bets) = if is_queued {
+        (Uint128::zero(), Vec::new())
+    } else {
+        match_orders(&mut deps, &env, &order)?
+    };
+
+    // Fill-or-kill orders revert the whole transaction unless completely matched
+    if order.time_in_force == TimeInForce::FillOrKill && matched_amount < amount {
+        return Err(ContractError::OrderNotFilled {});
+    }
+
+    // Update order status based on matching result
+    let mut updated_order = ORDERS.load(deps.storage, order_id)?;
+    if matched_amount == amount {
+        updated_order.status = OrderStatus::Filled;
+        updated_order.amount = matched_amount;  // Set amount to matched amount
+    } else if is_market_order {
+        // Market orders never rest on the book, the unmatched remainder is cancelled
+        updated_order.status = OrderStatus::Canceled;
+        updated_order.amount = matched_amount;
+    } else if matched_amount > Uint128::zero() {
+        updated_order.status = OrderStatus::PartiallyFilled;
+    }
+    updated_order.filled_amount = matched_amount;
+    save_order(deps.storage, &updated_order)?;
+
+    // Collateral actually locked: matched bets at their matched odds plus whatever rests on the book
+    let resting_amount = updated_order.amount - updated_order.filled_amount;
+    let locked_amount = match side {
+        OrderSide::Back => matched_amount + resting_amount,
+        OrderSide::Lay => matched_bets.iter()
+            .map(|bet| bet.amount.multiply_ratio(bet.odds - 100, 100u128))
+            .sum::<Uint128>() + resting_amount.multiply_ratio(odds - 100, 100u128),
+    };
+    let balance = debit_balance(deps.storage, &info.sender, locked_amount)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "place_order")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attribute("matched_amount", matched_amount.to_string())
+        .add_attribute("remaining_matched_bets", matched_bets.len().to_string())
+        .add_attribute("locked_amount", locked_amount.to_string())
+        .add_attribute("balance", balance.to_string()))
+}
+
+pub fn cancel_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+
+    // Check if the order belongs to the sender, expired orders can be cleaned up by anyone
+    if order.creator != info.sender && !order.is_expired(env.block.time.seconds()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Check if the order can be cancelled
+    if order.status != OrderStatus::Open && order.status != OrderStatus::PartiallyFilled {
+        return Err(ContractError::OrderNotCancellable {});
+    }
+
+    // Calculate refund amount
+    let refund_amount = match order.side {
+        OrderSide::Back => order.amount - order.filled_amount,
+        OrderSide::Lay => (order.amount - order.filled_amount).multiply_ratio(order.odds - 100, 100u128),
+    };
+
+    // Update order status
+    order.status = OrderStatus::Canceled;
+    order.amount = order.filled_amount;  // Set the amount to the filled amount
+    save_order(deps.storage, &order)?;
+
+    // Return the collateral to the creator's balance
+    credit_balance(deps.storage, &order.creator, refund_amount)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "cancel_order")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string()))
+}
+
+pub fn cash_out(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    option_id: u8,
+    worst_odds: Option<u32>,
+) -> Result<Response, ContractError> {
+    let quote = query_cash_out_quote(deps.as_ref(), &env, info.sender.clone(), market_id, option_id)?;
+    let side = quote.side.ok_or(ContractError::CashOutUnavailable {})?;
+
+    // The hedge must be matched in full within the slippage bound or not at all
+    let order_type = OrderType::Market { worst_odds: Some(worst_odds.unwrap_or(quote.odds)) };
+    let order_res = place_order(deps, env, info, OrderParams {
+        market_id,
+        option_id,
+        order_type,
+        side: side.clone(),
+        amount: quote.amount,
+        odds: 0,
+        time_in_force: Some(TimeInForce::FillOrKill),
+        keep_on_suspend: false,
+    })?;
+
+    Ok(Response::new()
+        .add_submessages(order_res.messages)
+        .add_attribute("method", "cash_out")
+        .add_attributes(order_res.attributes.into_iter().filter(|attr| attr.key != "method"))
+        .add_attribute("side", side.to_string())
+        .add_attribute("quoted_odds", quote.odds.to_string())
+        .add_attribute("quoted_profit", quote.profit.to_string()))
+}
+
+/// Returns the resting orders on the opposite side of `order` that it can match,
+/// in price-time priority, skipping expired orders
+fn matchable_orders<'a>(storage: &'a dyn Storage, env: &Env, order: &Order) -> impl Iterator<Item = StdResult<Order>> + 'a {
+    let now = env.block.time.seconds();
+    let opposite_side = if order.side == OrderSide::Back { OrderSide::Lay } else { OrderSide::Back };
+    // A backer takes lay orders with odds at least its own, a layer back orders with odds at most its own
+    let max_rank = opposite_side.price_rank(order.odds);
+    ORDER_BOOK
+        .prefix((order.market_id, (order.option_id, opposite_side.key())))
+        .keys(storage, None, Some(Bound::inclusive((max_rank, u64::MAX))), cosmwasm_std::Order::Ascending)
+        .map(move |key| {
+            let (_, order_id) = key?;
+            ORDERS.load(storage, order_id)
+        })
+        .filter(move |r| !matches!(r, Ok(resting) if resting.is_expired(now)))
+}
+
+/// Returns whether `order` would immediately match a resting order on the opposite side
+fn crosses_book(deps: Deps, env: &Env, order: &Order) -> StdResult<bool> {
+    Ok(matchable_orders(deps.storage, env, order).next().transpose()?.is_some())
+}
+
+pub fn match_orders(deps: &mut DepsMut, env: &Env, new_order: &Order) -> Result<(Uint128, Vec<MatchedBet>), ContractError> {
+    let platform_fee = MARKETS.load(deps.storage, new_order.market_id)?.platform_fee;
+    let mut matched_amount = Uint128::zero();
+    let mut matched_bets = Vec::new();
+
+    // Walk only the crossing price levels of the opposite side, best price first.
+    // Orders are collected lazily so the walk stops as soon as the new order is filled.
+    let mut matched_orders = Vec::new();
+    for order in matchable_orders(deps.storage, env, new_order) {
+        if matched_amount == new_order.amount {
+            break;
+        }
+        let order = order?;
+        let match_amount = std::cmp::min(new_order.amount - matched_amount, order.amount - order.filled_amount);
+        matched_amount += match_amount;
+        matched_orders.push((order, match_amount));
+    }
+
+    for (mut order, match_amount) in matched_orders {
+        let matched_bet = record_matched_bet(deps.storage, platform_fee, MatchedBet {
+            id: 0,
+            market_id: new_order.market_id,
+            option_id: new_order.option_id,
+            amount: match_amount,
+            odds: order.odds,
+            timestamp: env.block.time.seconds(),
+            back_user: if new_order.side == OrderSide::Back { new_order.creator.clone() } else { order.creator.clone() },
+            lay_user: if new_order.side == OrderSide::Lay { new_order.creator.clone() } else { order.creator.clone() },
+            redeemed: false,
+        })?;
+        matched_bets.push(matched_bet);
+
+        order.filled_amount += match_amount;
+
+        if order.filled_amount == order.amount {
+            order.status = OrderStatus::Filled;
+        } else {
+            order.status = OrderStatus::PartiallyFilled;
+        }
+
+        save_order(deps.storage, &order)?;
+    }
+
+    let mut updated_new_order = new_order.clone();
+    updated_new_order.filled_amount = matched_amount;
+    if matched_amount == new_order.amount {
+        updated_new_order.status = OrderStatus::Filled;
+    } else if matched_amount > Uint128::zero() {
+        updated_new_order.status = OrderStatus::PartiallyFilled;
+    }
+    save_order(deps.storage, &updated_new_order)?;
+
+    Ok((matched_amount, matched_bets))
+}
+
+/// Assigns the next id to a new matched bet, saves it with its indexes and updates both positions
+fn record_matched_bet(storage: &mut dyn Storage, platform_fee: Uint128, mut matched_bet: MatchedBet) -> StdResult<MatchedBet> {
+    let matched_bet_id = MATCHED_BET_COUNT.load(storage)? + 1;
+    matched_bet.id = matched_bet_id;
+
+    MATCHED_BETS.save(storage, matched_bet_id, &matched_bet)?;
+    MATCHED_BET_COUNT.save(storage, &matched_bet_id)?;
+    LAST_MATCHED_ODDS.save(storage, (matched_bet.market_id, matched_bet.option_id), &matched_bet.odds)?;
+    USER_MATCHED_BETS.save(storage, (matched_bet.back_user.clone(), matched_bet_id), &matched_bet.market_id)?;
+    USER_MATCHED_BETS.save(storage, (matched_bet.lay_user.clone(), matched_bet_id), &matched_bet.market_id)?;
+    update_positions(storage, &matched_bet, platform_fee)?;
+
+    Ok(matched_bet)
+}
+
+/// Clears the orders queued before the market opened with a single-price call auction per option.
+/// Options that are not active do not trade: a voided option's orders were refunded when it was
+/// voided, a suspended option keeps its queued orders and is uncrossed when it resumes.
+fn run_opening_auction(storage: &mut dyn Storage, env: &Env, market: &mut Market) -> StdResult<Vec<(u8, u32, Uint128)>> {
+    let now = env.block.time.seconds();
+    let mut results = Vec::new();
+
+    for option_id in 0..market.options.len() as u8 {
+        if market.option_statuses[option_id as usize] != OptionStatus::Active {
+            continue;
+        }
+        if let Some((auction_odds, volume)) = uncross_option(storage, now, market, option_id)? {
+            results.push((option_id, auction_odds, volume));
+        }
+    }
+
+    market.opening_auction = false;
+    MARKETS.save(storage, market.id, market)?;
+
+    Ok(results)
+}
+
+/// Runs the call auction of one option over its resting orders, returning the auction odds and volume.
+/// The auction odds maximise the matched stake, then minimise the unmatched imbalance, then are the
+/// lowest such odds. Every crossing order matches at those odds in price-time priority.
+fn uncross_option(storage: &mut dyn Storage, now: u64, market: &Market, option_id: u8) -> StdResult<Option<(u32, Uint128)>> {
+    let queued = |side: OrderSide| -> StdResult<Vec<Order>> {
+        let mut orders = Vec::new();
+        for key in ORDER_BOOK
+            .prefix((market.id, (option_id, side.key())))
+            .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+        {
+            let order = ORDERS.load(storage, key?.1)?;
+            if !order.is_expired(now) {
+                orders.push(order);
+            }
+        }
+        Ok(orders)
+    };
+    let backs = queued(OrderSide::Back)?;
+    let lays = queued(OrderSide::Lay)?;
+
+    // A back order takes any odds at or above its own, a lay order any odds at or below its own
+    let mut candidates: Vec<u32> = backs.iter().chain(lays.iter()).map(|order| order.odds).collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut auction: Option<(u32, Uint128, Uint128)> = None;
+    for odds in candidates {
+        let back_stake: Uint128 = backs.iter().filter(|order| order.odds <= odds).map(|order| order.amount - order.filled_amount).sum();
+        let lay_stake: Uint128 = lays.iter().filter(|order| order.odds >= odds).map(|order| order.amount - order.filled_amount).sum();
+        let volume = std::cmp::min(back_stake, lay_stake);
+        let imbalance = std::cmp::max(back_stake, lay_stake) - volume;
+        if volume.is_zero() {
+            continue;
+        }
+        // Candidates are ascending, so ties keep the lowest odds
+        let better = match auction {
+            None => true,
+            Some((_, best_volume, best_imbalance)) => {
+                volume > best_volume || (volume == best_volume && imbalance < best_imbalance)
+            }
+        };
+        if better {
+            auction = Some((odds, volume, imbalance));
+        }
+    }
+    let (auction_odds, volume, _) = match auction {
+        Some(auction) => auction,
+        None => return Ok(None),
+    };
+
+    // Allocate the volume to each side in price-time priority
+    let allocate = |orders: Vec<Order>| -> Vec<(Order, Uint128)> {
+        let mut left = volume;
+        orders
+            .into_iter()
+            .map_while(|order| {
+                let fill = std::cmp::min(left, order.amount - order.filled_amount);
+                left -= fill;
+                (!fill.is_zero()).then_some((order, fill))
+            })
+            .collect()
+    };
+    let back_fills = allocate(backs.into_iter().filter(|order| order.odds <= auction_odds).collect());
+    let lay_fills = allocate(lays.into_iter().filter(|order| order.odds >= auction_odds).collect());
+
+    // Pair the fills into matched bets at the auction odds
+    let mut back_left: Vec<Uint128> = back_fills.iter().map(|(_, fill)| *fill).collect();
+    let mut lay_left: Vec<Uint128> = lay_fills.iter().map(|(_, fill)| *fill).collect();
+    let mut lay_liability = vec![Uint128::zero(); lay_fills.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < back_fills.len() && j < lay_fills.len() {
+        let amount = std::cmp::min(back_left[i], lay_left[j]);
+        let matched_bet = record_matched_bet(storage, market.platform_fee, MatchedBet {
+            id: 0,
+            market_id: market.id,
+            option_id,
+            amount,
+            odds: auction_odds,
+            timestamp: now,
+            back_user: back_fills[i].0.creator.clone(),
+            lay_user: lay_fills[j].0.creator.clone(),
+            redeemed: false,
+        })?;
+        lay_liability[j] += matched_bet.amount.multiply_ratio(auction_odds - 100, 100u128);
+        back_left[i] -= amount;
+        lay_left[j] -= amount;
+        if back_left[i].is_zero() {
+            i += 1;
+        }
+        if lay_left[j].is_zero() {
+            j += 1;
+        }
+    }
+
+    for (mut order, fill) in back_fills {
+        order.filled_amount += fill;
+        order.status = if order.filled_amount == order.amount { OrderStatus::Filled } else { OrderStatus::PartiallyFilled };
+        save_order(storage, &order)?;
+    }
+    for ((mut order, fill), liability) in lay_fills.into_iter().zip(lay_liability) {
+        // Lay orders locked their liability at their own odds, the difference is returned
+        let locked = fill.multiply_ratio(order.odds - 100, 100u128);
+        credit_balance(storage, &order.creator, locked - liability)?;
+        order.filled_amount += fill;
+        order.status = if order.filled_amount == order.amount { OrderStatus::Filled } else { OrderStatus::PartiallyFilled };
+        save_order(storage, &order)?;
+    }
+
+    Ok(Some((auction_odds, volume)))
+}
+
+/// Adds a matched bet to the positions of both of its sides and credits back any
+/// collateral that the combined position no longer puts at risk
+fn update_positions(storage: &mut dyn Storage, matched_bet: &MatchedBet, platform_fee: Uint128) -> StdResult<()> {
+    let liability = matched_bet.amount.multiply_ratio(matched_bet.odds - 100, 100u128);
+
+    for side in [OrderSide::Back, OrderSide::Lay] {
+        let user = if side == OrderSide::Back { &matched_bet.back_user } else { &matched_bet.lay_user };
+        let key = (user.clone(), matched_bet.market_id, matched_bet.option_id);
+        let mut position = POSITIONS.may_load(storage, key.clone())?
+            .unwrap_or_else(|| Position::new(user.clone(), matched_bet.market_id, matched_bet.option_id));
+
+        match side {
+            OrderSide::Back => {
+                position.back_stake += matched_bet.amount;
+                position.back_payout += matched_bet.amount + liability;
+            }
+            OrderSide::Lay => {
+                position.lay_stake += matched_bet.amount;
+                position.lay_liability += liability;
+            }
+        }
+
+        let releasable = position.releasable(platform_fee);
+        if releasable > position.released {
+            credit_balance(storage, user, releasable - position.released)?;
+            position.released = releasable;
+        }
+        POSITIONS.save(storage, key, &position)?;
+    }
+
+    Ok(())
+}
+
+/// Deducts collateral already released on a position from an amount about to be paid out on it
+fn reclaim_released(storage: &mut dyn Storage, user: &Addr, market_id: u64, option_id: u8, amount: Uint128) -> StdResult<Uint128> {
+    let key = (user.clone(), market_id, option_id);
+    let mut position = match POSITIONS.may_load(storage, key.clone())? {
+        Some(position) => position,
+        None => return Ok(amount),
+    };
+
+    let reclaim = std::cmp::min(position.released - position.reclaimed, amount);
+    if reclaim.is_zero() {
+        return Ok(amount);
+    }
+    position.reclaimed += reclaim;
+    POSITIONS.save(storage, key, &position)?;
+
+    Ok(amount - reclaim)
+}
+
+pub fn redeem_winnings(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    matched_bet_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Redemptions)?;
+    let mut matched_bet = MATCHED_BETS.load(deps.storage, matched_bet_id)?;
+    let market = MARKETS.load(deps.storage, matched_bet.market_id)?;
+
+    // Check if market is resolved
+    if market.status != MarketStatus::Resolved {
+        return Err(ContractError::MarketNotResolved {});
+    }
+
+    // Check if bet is already redeemed
+    if matched_bet.redeemed {
+        return Err(ContractError::AlreadyRedeemed {});
+    }
+
+    // Check if the caller is the winner, then settle the bet net of the platform fee
+    let (winnings, fee) = settle_matched_bet(deps.storage, &market, &mut matched_bet, &info.sender)?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    // Credit winnings to the winner's balance
+    credit_balance(deps.storage, &info.sender, winnings)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "redeem_winnings")
+        .add_attribute("matched_bet_id", matched_bet_id.to_string())
+        .add_attribute("winnings", winnings.to_string())
+        .add_attribute("fee", fee.to_string()))
+}
+
+pub fn redeem_market(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Redemptions)?;
+    let market = MARKETS.load(deps.storage, market_id)?;
+
+    // Check if market is resolved
+    if market.status != MarketStatus::Resolved {
+        return Err(ContractError::MarketNotResolved {});
+    }
+
+    let matched_bet_ids: Vec<u64> = USER_MATCHED_BETS
+        .prefix(info.sender.clone())
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .filter(|r| !matches!(r, Ok((_, bet_market_id)) if *bet_market_id != market_id))
+        .map(|r| r.map(|(matched_bet_id, _)| matched_bet_id))
+        .collect::<StdResult<Vec<u64>>>()?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "redeem_market")
+        .add_attribute("market_id", market_id.to_string());
+    let mut total_winnings = Uint128::zero();
+    let mut total_fee = Uint128::zero();
+    let mut redeemed_count = 0u64;
+
+    for matched_bet_id in matched_bet_ids {
+        let mut matched_bet = MATCHED_BETS.load(deps.storage, matched_bet_id)?;
+        if let Some((winnings, fee)) = settle_matched_bet(deps.storage, &market, &mut matched_bet, &info.sender)? {
+            total_winnings += winnings;
+            total_fee += fee;
+            redeemed_count += 1;
+            response = response.add_attribute("redeemed", format!("{}:{}", matched_bet_id, winnings));
+        }
+    }
+
+    if redeemed_count == 0 {
+        return Err(ContractError::NothingToRedeem {});
+    }
+
+    // Credit all winnings to the winner's balance at once
+    credit_balance(deps.storage, &info.sender, total_winnings)?;
+
+    Ok(response
+        .add_attribute("redeemed_count", redeemed_count.to_string())
+        .add_attribute("winnings", total_winnings.to_string())
+        .add_attribute("fee", total_fee.to_string()))
+}
+
+pub fn redeem_all(
+    deps: DepsMut,
+    info: MessageInfo,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Redemptions)?;
+    let limit = limit.unwrap_or(30) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let matched_bet_ids: Vec<u64> = USER_MATCHED_BETS
+        .prefix(info.sender.clone())
+        .keys(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<u64>>>()?;
+
+    let mut response = Response::new().add_attribute("method", "redeem_all");
+    let mut total_winnings = Uint128::zero();
+    let mut total_fee = Uint128::zero();
+    let mut redeemed_count = 0u64;
+
+    for matched_bet_id in &matched_bet_ids {
+        let mut matched_bet = MATCHED_BETS.load(deps.storage, *matched_bet_id)?;
+        let market = MARKETS.load(deps.storage, matched_bet.market_id)?;
+        if let Some((winnings, fee)) = settle_matched_bet(deps.storage, &market, &mut matched_bet, &info.sender)? {
+            total_winnings += winnings;
+            total_fee += fee;
+            redeemed_count += 1;
+            response = response.add_attribute("redeemed", format!("{}:{}", matched_bet_id, winnings));
+        }
+    }
+
+    if !total_winnings.is_zero() {
+        credit_balance(deps.storage, &info.sender, total_winnings)?;
+    }
+
+    // The last bet looked at is where the next page starts
+    if let Some(last_matched_bet_id) = matched_bet_ids.last() {
+        response = response.add_attribute("last_matched_bet_id", last_matched_bet_id.to_string());
+    }
+
+    Ok(response
+        .add_attribute("redeemed_count", redeemed_count.to_string())
+        .add_attribute("winnings", total_winnings.to_string())
+        .add_attribute("fee", total_fee.to_string()))
+}
+
+/// Settles the winning side of a matched bet held by `user` and marks the bet redeemed.
+/// The fee was already accrued when the market resolved. Returns the winnings, less any collateral released early on the user's position, and the fee,
+/// or None if the bet is not a winning, unredeemed bet of the user.
+fn settle_matched_bet(
+    storage: &mut dyn Storage,
+    market: &Market,
+    matched_bet: &mut MatchedBet,
+    user: &Addr,
+) -> StdResult<Option<(Uint128, Uint128)>> {
+    let result = match market.result {
+        Some(result) if market.status == MarketStatus::Resolved => result,
+        _ => return Ok(None),
+    };
+    if matched_bet.redeemed {
+        return Ok(None);
+    }
+
+    let is_winner = matched_bet.option_id == result;
+    let winner = if is_winner { &matched_bet.back_user } else { &matched_bet.lay_user };
+    if winner != user {
+        return Ok(None);
+    }
+
+    let (winnings, fee) = calculate_winnings(matched_bet, is_winner, market.platform_fee);
+    let winnings = reclaim_released(storage, user, matched_bet.market_id, matched_bet.option_id, winnings)?;
+
+    matched_bet.redeemed = true;
+    MATCHED_BETS.save(storage, matched_bet.id, matched_bet)?;
+
+    Ok(Some((winnings, fee)))
+}
+
+/// Returns the payout for the winning side of a matched bet and the platform fee
+/// withheld from it. The winner receives the whole pot (back stake plus lay liability)
+/// and the fee is charged on the net profit only, never on the returned stake.
+pub fn calculate_winnings(matched_bet: &MatchedBet, is_winner: bool, platform_fee: Uint128) -> (Uint128, Uint128) {
+    let pot = matched_bet.amount.multiply_ratio(matched_bet.odds, 100u128);
+    let profit = if is_winner {
+        // Back side wins the lay liability
+        pot - matched_bet.amount
+    } else {
+        // Lay side wins the back stake
+        matched_bet.amount
+    };
+    let fee = profit.multiply_ratio(platform_fee, 10000u128);
+    (pot - fee, fee)
+}
+
+/// Accrues the platform fee of every unsettled bet of a market once its result is known. Winnings
+/// may be paid out of collateral released early, so the fees cannot wait for the bets to be redeemed.
+fn accrue_resolution_fees(storage: &mut dyn Storage, market: &Market) -> StdResult<()> {
+    let result = match market.result {
+        Some(result) => result,
+        None => return Ok(()),
+    };
+
+    let mut fees = Uint128::zero();
+    for item in MATCHED_BETS
+        .idx
+        .market
+        .prefix(market.id)
+        .range(storage, None, None, cosmwasm_std::Order::Ascending)
+    {
+        let (_, matched_bet) = item?;
+        if !matched_bet.redeemed {
+            fees += calculate_winnings(&matched_bet, matched_bet.option_id == result, market.platform_fee).1;
+        }
+    }
+    accrue_market_fee(storage, market.id, fees)
+}
+
+fn accrue_market_fee(storage: &mut dyn Storage, market_id: u64, mut fee: Uint128) -> StdResult<()> {
+    if fee.is_zero() {
+        return Ok(());
+    }
+
+    // The voters of a disputed market receive their share of its fees
+    if let Some(mut pool) = VOTER_REWARD_POOLS.may_load(storage, market_id)? {
+        let voters_fee = fee.multiply_ratio(pool.fee_share, 10000u128);
+        pool.fee_rewards += voters_fee;
+        VOTER_REWARD_POOLS.save(storage, market_id, &pool)?;
+        fee -= voters_fee;
+    }
+
+    MARKET_FEES.update(storage, market_id, |fees| -> StdResult<MarketFees> {
+        let mut fees = fees.unwrap_or(MarketFees {
+            market_id,
+            collected: Uint128::zero(),
+            pending: Uint128::zero(),
+        });
+        fees.pending += fee;
+        Ok(fees)
+    })?;
+    Ok(())
+}
+
+pub fn sweep_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only fee managers can sweep fees
+    if !has_role(deps.storage, &config, &info.sender, Role::FeeManager) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut fees = MARKET_FEES.may_load(deps.storage, market_id)?
+        .ok_or(ContractError::NoPendingFees {})?;
+    if fees.pending.is_zero() {
+        return Err(ContractError::NoPendingFees {});
+    }
+
+    let amount = fees.pending;
+    fees.collected += amount;
+    fees.pending = Uint128::zero();
+    MARKET_FEES.save(deps.storage, market_id, &fees)?;
+
+    // Send the accrued fees to the treasury
+    let send_msg = BankMsg::Send {
+        to_address: config.treasury.to_string(),
+        amount: vec![Coin {
+            denom: config.token_denom,
+            amount,
+        }],
+    };
+
+    Ok(Response::new()
+        .add_message(send_msg)
+        .add_attribute("method", "sweep_fees")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("treasury", config.treasury.to_string())
+        .add_attribute("amount", amount.to_string()))
+}
+
+
+pub fn add_to_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only whitelist managers can add to whitelist
+    if !has_role(deps.storage, &config, &info.sender, Role::WhitelistManager) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Fix Bug ID #9: Validate and normalize the address
+    let validated_address = deps.api.addr_validate(&address.to_string().to_lowercase())?;
+
+    // Check if the address is already whitelisted
+    if WHITELISTED_ADDRESSES.may_load(deps.storage, validated_address.clone())?.is_some() {
+        return Err(ContractError::AlreadyWhitelisted {});
+    }
+
+    WHITELISTED_ADDRESSES.save(deps.storage, validated_address.clone(), &true)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_to_whitelist")
+        .add_attribute("address", validated_address.to_string()))
+}
+
+pub fn remove_from_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only whitelist managers can remove from whitelist
+    if !has_role(deps.storage, &config, &info.sender, Role::WhitelistManager) {
+        return Err(ContractError::Unauthorized {});
+    }
+     // Fix Bug ID #9: Validate and normalize the address
+    let validated_address = deps.api.addr_validate(&address.to_string().to_lowercase())?;
+
+    WHITELISTED_ADDRESSES.remove(deps.storage, validated_address.clone());
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_from_whitelist")
+        .add_attribute("address", validated_address.to_string()))
+}
+
+pub fn propose_market_result(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    proposed_result: u8,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Disputes)?;
+    let mut market = MARKETS.load(deps.storage, market_id)?;
+
+    // Fix Bug ID #5: Ensure market is already properly closed
+    if market.status != MarketStatus::Closed {
+        return Err(ContractError::InvalidMarketState {});
+    }
+
+    // Fix Bug ID #3: Check if market is canceled
+    if market.status == MarketStatus::Canceled {
+        return Err(ContractError::InvalidMarketState {});
+    }
+
+    // Check if the market end time has passed
+    if env.block.time.seconds() <= market.end_time {
+        return Err(ContractError::MarketNotEnded {});
+    }
+
+    // Fix Bug ID #4: Check if any proposal exists regardless of status
+    if PROPOSALS.may_load(deps.storage, market_id)?.is_some() {
+        return Err(ContractError::ProposalAlreadyExists {});
+    }
+
+    // Check if the proposed result is an option that can win
+    if !market.can_resolve_to(proposed_result) {
+        return Err(ContractError::InvalidOption {});
+    }
+
+    // Check if the correct bond amount is sent
+    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom);
+    if sent_funds.is_none() || sent_funds.unwrap().amount != market.resolution_bond {
+        return Err(ContractError::IncorrectBondAmount {});
+    }
+
+    // Create and save proposal
+    let proposal = ResolutionProposal {
+        market_id,
+        proposer: info.sender.clone(),
+        proposed_result,
+        bond_amount: market.resolution_bond,
+        proposal_time: env.block.time.seconds(),
+        challenge_deadline: env.block.time.seconds() + config.challenging_period,
+        status: ProposalStatus::Active,
+        winner_bond_share: Uint128::zero(),
+        bond_redeemed: false,
+    };
+    PROPOSALS.save(deps.storage, market_id, &proposal)?;
+
+    // Update market status
+    market.status = MarketStatus::Closed;
+    MARKETS.save(deps.storage, market_id, &market)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "propose_market_result")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("proposed_result", proposed_result.to_string())
+        .add_attribute("proposer", info.sender.to_string())
+        .add_attribute("bond_amount", market.resolution_bond.to_string()))
+}
+
+pub fn raise_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    proposed_outcome: u8,
+    evidence: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Disputes)?;
+    let mut market = MARKETS.load(deps.storage, market_id)?;
+    let mut proposal = PROPOSALS.load(deps.storage, market_id)?;
+
+    // Check if proposal is active
+    if proposal.status != ProposalStatus::Active {
+        return Err(ContractError::InvalidProposalState {});
+    }
+
+    // Check if within challenging period
+    if env.block.time.seconds() > proposal.challenge_deadline {
+        return Err(ContractError::ChallengePeriodEnded {});
+    }
+
+    // Check if the proposed outcome is an option that can win
+    if !market.can_resolve_to(proposed_outcome) {
+        return Err(ContractError::InvalidOption {});
+    }
+
+    // Check if the correct bond amount is sent
+    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom);
+    if sent_funds.is_none() || sent_funds.unwrap().amount != market.resolution_bond {
+        return Err(ContractError::IncorrectBondAmount {});
+    }
+
+    // Create and save dispute
+    let dispute = Dispute {
+        market_id,
+        challenger: info.sender.clone(),
+        proposed_outcome,
+        evidence,
+        status: DisputeStatus::Active,
+        created_at: env.block.time.seconds(),
+        voting_deadline: Some(env.block.time.seconds() + config.voting_period),
+        reveal_deadline: reveal_deadline(&config, env.block.time.seconds() + config.voting_period),
+        snapshot_height: config.staking.stake_weighted_voting.then_some(env.block.height),
+        round: 1,
+        max_appeals: config.escalation.max_appeals,
+        appeal_deadline: None,
+    };
+    DISPUTES.save(deps.storage, market_id, &dispute)?;
+    DISPUTE_ROUNDS.save(deps.storage, (market_id, 1), &new_round(&dispute, proposal.proposed_result, market.resolution_bond))?;
+
+    // Update proposal status
+    proposal.status = ProposalStatus::Challenged;
+    PROPOSALS.save(deps.storage, market_id, &proposal)?;
+
+    market.status = MarketStatus::InDispute;
+    MARKETS.save(deps.storage, market_id, &market)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "raise_dispute")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("proposed_outcome", proposed_outcome.to_string())
+        .add_attribute("challenger", info.sender.to_string())
+        .add_attribute("bond_amount", market.resolution_bond.to_string()))
+}
+
+pub fn appeal_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    proposed_outcome: u8,
+    evidence: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Disputes)?;
+    let market = MARKETS.load(deps.storage, market_id)?;
+    let mut dispute = DISPUTES.load(deps.storage, market_id)?;
+
+    // Check if the round was decided and appeals are left
+    if dispute.status != DisputeStatus::Decided || dispute.round > dispute.max_appeals {
+        return Err(ContractError::NotAppealable {});
+    }
+    let previous = DISPUTE_ROUNDS.load(deps.storage, (market_id, dispute.round))?;
+    let challenged_outcome = previous.result.ok_or(ContractError::NotAppealable {})?;
+
+    // Check if within the appeal period
+    if dispute.appeal_deadline.is_some_and(|deadline| env.block.time.seconds() > deadline) {
+        return Err(ContractError::AppealPeriodEnded {});
+    }
+
+    // Check if the proposed outcome is an option that can win and overturns the round result
+    if !market.can_resolve_to(proposed_outcome) {
+        return Err(ContractError::InvalidOption {});
+    }
+    if proposed_outcome == challenged_outcome {
+        return Err(ContractError::InvalidAppeal {});
+    }
+
+    // Check if the escalated bond amount is sent
+    let bond = previous.bond * Uint128::from(config.escalation.bond_multiplier);
+    let sent_funds = info.funds.iter().find(|coin| coin.denom == config.token_denom);
+    if sent_funds.is_none_or(|coin| coin.amount != bond) {
+        return Err(ContractError::IncorrectBondAmount {});
+    }
+
+    // The next round is voted afresh, unless it is the final one which the resolvers decide
+    clear_votes(deps.storage, market_id)?;
+    let now = env.block.time.seconds();
+    dispute.round += 1;
+    dispute.challenger = info.sender.clone();
+    dispute.proposed_outcome = proposed_outcome;
+    dispute.evidence = evidence;
+    dispute.created_at = now;
+    dispute.appeal_deadline = None;
+    dispute.voting_deadline = Some(now + config.voting_period);
+    dispute.reveal_deadline = reveal_deadline(&config, now + config.voting_period);
+    dispute.snapshot_height = config.staking.stake_weighted_voting.then_some(env.block.height);
+    dispute.status = if dispute.round > dispute.max_appeals {
+        DisputeStatus::Escalated
+    } else {
+        DisputeStatus::Active
+    };
+    DISPUTES.save(deps.storage, market_id, &dispute)?;
+    DISPUTE_ROUNDS.save(deps.storage, (market_id, dispute.round), &new_round(&dispute, challenged_outcome, bond))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "appeal_dispute")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("round", dispute.round.to_string())
+        .add_attribute("proposed_outcome", proposed_outcome.to_string())
+        .add_attribute("challenger", info.sender.to_string())
+        .add_attribute("bond_amount", bond.to_string())
+        .add_attribute("final_round", (dispute.status == DisputeStatus::Escalated).to_string()))
+}
+
+/// End of the reveal window for a round voted until `voting_deadline`, when votes are committed and revealed
+fn reveal_deadline(config: &Config, voting_deadline: u64) -> Option<u64> {
+    match config.vote_mode {
+        VoteMode::Open => None,
+        VoteMode::CommitReveal { reveal_period } => Some(voting_deadline + reveal_period),
+    }
+}
+
+fn new_round(dispute: &Dispute, challenged_outcome: u8, bond: Uint128) -> DisputeRound {
+    DisputeRound {
+        market_id: dispute.market_id,
+        round: dispute.round,
+        challenger: dispute.challenger.clone(),
+        challenged_outcome,
+        proposed_outcome: dispute.proposed_outcome,
+        evidence: dispute.evidence.clone(),
+        bond,
+        created_at: dispute.created_at,
+        result: None,
+        votes: vec![],
+        vote_counts: vec![],
+        winner_bond_share: Uint128::zero(),
+        bond_redeemed: false,
+    }
+}
+
+/// Rounds of a market's dispute. Disputes raised before rounds were recorded have their single
+/// round rebuilt from the dispute and the proposal.
+fn load_dispute_rounds(storage: &dyn Storage, market_id: u64, proposal: &ResolutionProposal) -> StdResult<Vec<DisputeRound>> {
+    let rounds = DISPUTE_ROUNDS
+        .prefix(market_id)
+        .range(storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(_, round)| round))
+        .collect::<StdResult<Vec<DisputeRound>>>()?;
+    if !rounds.is_empty() {
+        return Ok(rounds);
+    }
+
+    Ok(DISPUTES
+        .may_load(storage, market_id)?
+        .map(|dispute| DisputeRound {
+            winner_bond_share: proposal.winner_bond_share,
+            bond_redeemed: proposal.bond_redeemed,
+            ..new_round(&dispute, proposal.proposed_result, proposal.bond_amount)
+        })
+        .into_iter()
+        .collect())
+}
+
+/// Records the result and the votes of the current round
+fn close_round(storage: &mut dyn Storage, proposal: &ResolutionProposal, result: Option<u8>) -> StdResult<()> {
+    let mut round = load_dispute_rounds(storage, proposal.market_id, proposal)?
+        .pop()
+        .ok_or_else(|| StdError::not_found("DisputeRound"))?;
+    let (votes, vote_counts) = load_votes(storage, proposal.market_id)?;
+    round.result = result;
+    round.votes = votes;
+    round.vote_counts = vote_counts;
+    DISPUTE_ROUNDS.save(storage, (proposal.market_id, round.round), &round)
+}
+
+fn clear_votes(storage: &mut dyn Storage, market_id: u64) -> StdResult<()> {
+    let voters = VOTES
+        .prefix(market_id)
+        .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    for voter in voters {
+        VOTES.remove(storage, (market_id, voter));
+    }
+    let outcomes = VOTE_COUNTS
+        .prefix(market_id)
+        .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<u8>>>()?;
+    for outcome in outcomes {
+        VOTE_COUNTS.remove(storage, (market_id, outcome));
+    }
+    let committers = VOTE_COMMITS
+        .prefix(market_id)
+        .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    for committer in committers {
+        VOTE_COMMITS.remove(storage, (market_id, committer));
+    }
+    Ok(())
+}
+
+pub fn cast_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    vote: u8,
+) -> Result<Response, ContractError> {
+    let (market, dispute, weight) = load_dispute_for_voting(deps.as_ref(), &env, &info, market_id)?;
+
+    if dispute.reveal_deadline.is_some() {
+        return Err(ContractError::CommitRevealRequired {});
+    }
+
+    // Check if voter has already voted
+    if VOTES.has(deps.storage, (market_id, info.sender.clone())) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+
+    record_vote(deps.storage, &market, &info.sender, vote, weight)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "cast_vote")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("voter", info.sender)
+        .add_attribute("vote", vote.to_string())
+        .add_attribute("weight", weight.to_string()))
+}
+
+pub fn commit_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    hash: String,
+) -> Result<Response, ContractError> {
+    let (_, dispute, _) = load_dispute_for_voting(deps.as_ref(), &env, &info, market_id)?;
+
+    if dispute.reveal_deadline.is_none() {
+        return Err(ContractError::CommitRevealNotEnabled {});
+    }
+
+    // A hash that is not a sha256 digest could never be revealed
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidVoteHash {});
+    }
+
+    // Check if voter has already committed
+    if VOTE_COMMITS.has(deps.storage, (market_id, info.sender.clone())) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+
+    VOTE_COMMITS.save(deps.storage, (market_id, info.sender.clone()), &hash.to_lowercase())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "commit_vote")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("voter", info.sender))
+}
+
+pub fn reveal_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    vote: u8,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Disputes)?;
+    let market = MARKETS.load(deps.storage, market_id)?;
+    let dispute = DISPUTES.load(deps.storage, market_id)?;
+
+    let reveal_deadline = dispute.reveal_deadline.ok_or(ContractError::CommitRevealNotEnabled {})?;
+
+    // Check if market is in disputed state
+    if market.status != MarketStatus::InDispute {
+        return Err(ContractError::InvalidMarketState {});
+    }
+
+    // Check if within the reveal window, which follows the voting period
+    let now = env.block.time.seconds();
+    if now <= dispute.voting_end(config.voting_period) {
+        return Err(ContractError::RevealPeriodNotStarted {});
+    }
+    if now > reveal_deadline {
+        return Err(ContractError::RevealPeriodEnded {});
+    }
+
+    let commit = VOTE_COMMITS
+        .may_load(deps.storage, (market_id, info.sender.clone()))?
+        .ok_or(ContractError::NoCommittedVote {})?;
+    if commit != vote_commitment(market_id, &info.sender, vote, &salt) {
+        return Err(ContractError::InvalidReveal {});
+    }
+
+    let weight = voting_power(deps.storage, &dispute, &info.sender)?;
+    record_vote(deps.storage, &market, &info.sender, vote, weight)?;
+    VOTE_COMMITS.remove(deps.storage, (market_id, info.sender.clone()));
+
+    Ok(Response::new()
+        .add_attribute("method", "reveal_vote")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("voter", info.sender)
+        .add_attribute("vote", vote.to_string())
+        .add_attribute("weight", weight.to_string()))
+}
+
+/// Loads a disputed market and its dispute, checking that the sender can vote on it now.
+/// Also returns the sender's voting power.
+fn load_dispute_for_voting(deps: Deps, env: &Env, info: &MessageInfo, market_id: u64) -> Result<(Market, Dispute, u64), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Disputes)?;
+    let market = MARKETS.load(deps.storage, market_id)?;
+    let dispute = DISPUTES.load(deps.storage, market_id)?;
+
+    // Check if voter is whitelisted or has stake at the snapshot
+    let weight = voting_power(deps.storage, &dispute, &info.sender)?;
+
+    // Check if market is in disputed state
+    if market.status != MarketStatus::InDispute {
+        return Err(ContractError::InvalidMarketState {});
+    }
+
+    // Escalated rounds are left to the resolvers
+    if dispute.status == DisputeStatus::Escalated {
+        return Err(ContractError::DisputeEscalated {});
+    }
+
+    // Check if within voting period
+    if dispute.status != DisputeStatus::Active || env.block.time.seconds() > dispute.voting_end(config.voting_period) {
+        return Err(ContractError::VotingPeriodEnded {});
+    }
+
+    Ok((market, dispute, weight))
+}
+
+/// One vote per whitelisted address, or the stake at the dispute's snapshot with stake-weighted voting
+fn voting_power(storage: &dyn Storage, dispute: &Dispute, voter: &Addr) -> Result<u64, ContractError> {
+    match dispute.snapshot_height {
+        None => {
+            if !WHITELISTED_ADDRESSES.has(storage, voter.clone()) {
+                return Err(ContractError::NotWhitelisted {});
+            }
+            Ok(1)
+        }
+        Some(height) => {
+            let stake = STAKES.may_load_at_height(storage, voter.clone(), height)?.unwrap_or_default();
+            if stake.is_zero() {
+                return Err(ContractError::NoVotingPower {});
+            }
+            Ok(u64::try_from(stake.u128()).unwrap_or(u64::MAX))
+        }
+    }
+}
+
+fn record_vote(storage: &mut dyn Storage, market: &Market, voter: &Addr, vote: u8, weight: u64) -> Result<(), ContractError> {
+    // Fix Bug ID #7: Validate vote is an option that can win or the void outcome
+    if vote != VOID_OUTCOME && !market.can_resolve_to(vote) {
+        return Err(ContractError::InvalidVote {});
+    }
+
+    // Save vote
+    let vote_record = Vote {
+        voter: voter.clone(),
+        option_id: vote,
+        weight,
+    };
+    VOTES.save(storage, (market.id, voter.clone()), &vote_record)?;
+
+    // Update vote count
+    VOTE_COUNTS.update(storage, (market.id, vote), |count| -> StdResult<u64> {
+        Ok(count.unwrap_or(0).saturating_add(weight))
+    })?;
+
+    Ok(())
+}
+
+/// Hex encoded sha256 of "{market_id}:{voter}:{outcome}:{salt}". The voter is part of the
+/// preimage so that a commit cannot be copied and revealed by someone else.
+pub fn vote_commitment(market_id: u64, voter: &Addr, outcome: u8, salt: &str) -> String {
+    let digest = Sha256::digest(format!("{}:{}:{}:{}", market_id, voter, outcome, salt).as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn resolve_dispute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Disputes)?;
+    let mut market = MARKETS.load(deps.storage, market_id)?;
+    let mut proposal = PROPOSALS.load(deps.storage, market_id)?;
+
+    // Only resolvers can resolve disputes
+    if !has_role(deps.storage, &config, &info.sender, Role::Resolver) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut messages = vec![];
+    let mut attributes = vec![
+        ("method".to_string(), "resolve_dispute".to_string()),
+        ("market_id".to_string(), market_id.to_string()),
+    ];
+
+    if proposal.status == ProposalStatus::Active {
+        // No dispute case
+        if env.block.time.seconds() <= proposal.challenge_deadline {
+            return Err(ContractError::ChallengePeriodNotEnded {});
+        }
+
+        // Resolve in favor of the proposer
+        market.status = MarketStatus::Resolved;
         market.result = Some(proposal.proposed_result);
         proposal.status = ProposalStatus::Resolved;
+        accrue_resolution_fees(deps.storage, &market)?;
 
         // Send reward to proposer
         messages.push(CosmosMsg::Bank(BankMsg::Send {
@@ -1011,48 +2645,341 @@ pub fn resolve_dispute(
         attributes.push(("winner".to_string(), proposal.proposer.to_string()));
     } else if proposal.status == ProposalStatus::Challenged {
         // Disputed case
-        let dispute = DISPUTES.load(deps.storage, market_id)?;
-        if env.block.time.seconds() <= proposal.challenge_deadline + config.voting_period {
-            return Err(ContractError::VotingPeriodNotEnded {});
+        let mut dispute = DISPUTES.load(deps.storage, market_id)?;
+        let now = env.block.time.seconds();
+        let winning_outcome = match dispute.status {
+            DisputeStatus::Escalated => return Err(ContractError::DisputeEscalated {}),
+            DisputeStatus::Resolved => return Err(ContractError::InvalidProposalState {}),
+            DisputeStatus::Decided => {
+                // The round result stands once nobody appealed it in time
+                if dispute.appeal_deadline.is_some_and(|deadline| now <= deadline) {
+                    return Err(ContractError::AppealPeriodNotEnded {});
+                }
+                DISPUTE_ROUNDS.load(deps.storage, (market_id, dispute.round))?.result
+            }
+            DisputeStatus::Active => {
+                // Appealed rounds are voted from their own creation
+                if now <= std::cmp::max(proposal.challenge_deadline, dispute.created_at) + config.voting_period {
+                    return Err(ContractError::VotingPeriodNotEnded {});
+                }
+                if dispute.reveal_deadline.is_some_and(|deadline| now <= deadline) {
+                    return Err(ContractError::VotingPeriodNotEnded {});
+                }
+
+                // The fallbacks keep the result the round challenged, the proposal in the first round
+                let standing_outcome = match dispute.round {
+                    1 => proposal.proposed_result,
+                    round => DISPUTE_ROUNDS.load(deps.storage, (market_id, round))?.challenged_outcome,
+                };
+
+                // Count votes and apply the quorum, supermajority and tie rules
+                let (_, vote_counts) = query_votes(deps.as_ref(), market_id)?;
+                let rules = &config.dispute_rules;
+                let tally = tally_votes(rules, &vote_counts);
+                attributes.push(("rule".to_string(), tally.name().to_string()));
+                let round_outcome = match tally {
+                    VoteTally::Majority(outcome) => Some(outcome),
+                    VoteTally::Tie | VoteTally::NoSupermajority => {
+                        attributes.push(("fallback".to_string(), rules.tie_rule.to_string()));
+                        rules.tie_rule.outcome(standing_outcome)
+                    }
+                    VoteTally::NoQuorum => {
+                        attributes.push(("fallback".to_string(), rules.no_quorum_rule.to_string()));
+                        rules.no_quorum_rule.outcome(standing_outcome)
+                    }
+                };
+                close_round(deps.storage, &proposal, round_outcome)?;
+                attributes.push(("round".to_string(), dispute.round.to_string()));
+
+                // A decided round can be appealed while appeals are left
+                match round_outcome {
+                    Some(outcome) if dispute.round <= dispute.max_appeals => {
+                        let appeal_deadline = now + config.escalation.appeal_period;
+                        dispute.status = DisputeStatus::Decided;
+                        dispute.appeal_deadline = Some(appeal_deadline);
+                        DISPUTES.save(deps.storage, market_id, &dispute)?;
+                        attributes.push(("result".to_string(), "round_decided".to_string()));
+                        attributes.push(("round_outcome".to_string(), outcome.to_string()));
+                        attributes.push(("appeal_deadline".to_string(), appeal_deadline.to_string()));
+                        None
+                    }
+                    _ => round_outcome,
+                }
+            }
+        };
+
+        match winning_outcome {
+            Some(winning_outcome) => {
+                let settled = settle_dispute(&mut deps, &env, &config, &mut market, &mut proposal, &mut dispute, winning_outcome)?;
+                messages.extend(settled.messages.into_iter().map(|sub_msg| sub_msg.msg));
+                attributes.extend(settled.attributes.into_iter().map(|attr| (attr.key, attr.value)));
+            }
+            None if dispute.status == DisputeStatus::Decided => {}
+            None => {
+                // Left to the resolvers, the market stays in dispute until they decide
+                dispute.status = DisputeStatus::Escalated;
+                DISPUTES.save(deps.storage, market_id, &dispute)?;
+                attributes.push(("result".to_string(), "dispute_escalated".to_string()));
+            }
         }
+    } else {
+        return Err(ContractError::InvalidProposalState {});
+    }
 
-        // Count votes
-        let (_votes, vote_counts) = query_votes(deps.as_ref(), market_id)?;
-        let winning_outcome = vote_counts.iter().max_by_key(|&(_, count)| count).map(|&(outcome, _)| outcome)
-            .ok_or(ContractError::NoVotes {})?;
+    MARKETS.save(deps.storage, market_id, &market)?;
+    PROPOSALS.save(deps.storage, market_id, &proposal)?;
 
-        market.status = MarketStatus::Resolved;
-        market.result = Some(winning_outcome);
-        proposal.status = ProposalStatus::Resolved;
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+pub fn resolve_escalated_dispute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    outcome: u8,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Disputes)?;
+    let mut market = MARKETS.load(deps.storage, market_id)?;
+    let mut proposal = PROPOSALS.load(deps.storage, market_id)?;
+    let mut dispute = DISPUTES.load(deps.storage, market_id)?;
+
+    // Only resolvers can decide escalated disputes
+    if !has_role(deps.storage, &config, &info.sender, Role::Resolver) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if dispute.status != DisputeStatus::Escalated {
+        return Err(ContractError::DisputeNotEscalated {});
+    }
 
-        // Determine the winner and send reward
-        let winner = if winning_outcome == proposal.proposed_result {
-            proposal.proposer.clone()
+    if outcome != VOID_OUTCOME && !market.can_resolve_to(outcome) {
+        return Err(ContractError::InvalidOption {});
+    }
+
+    close_round(deps.storage, &proposal, Some(outcome))?;
+    let response = settle_dispute(&mut deps, &env, &config, &mut market, &mut proposal, &mut dispute, outcome)?;
+
+    MARKETS.save(deps.storage, market_id, &market)?;
+    PROPOSALS.save(deps.storage, market_id, &proposal)?;
+
+    Ok(response
+        .add_attribute("method", "resolve_escalated_dispute")
+        .add_attribute("market_id", market_id.to_string()))
+}
+
+/// Result of counting the votes of a dispute against the configured rules
+enum VoteTally {
+    Majority(u8),
+    Tie,
+    NoSupermajority,
+    NoQuorum,
+}
+
+impl VoteTally {
+    fn name(&self) -> &'static str {
+        match self {
+            VoteTally::Majority(_) => "majority",
+            VoteTally::Tie => "tie",
+            VoteTally::NoSupermajority => "no_supermajority",
+            VoteTally::NoQuorum => "no_quorum",
+        }
+    }
+}
+
+fn tally_votes(rules: &DisputeRules, vote_counts: &[(u8, u64)]) -> VoteTally {
+    let total: u64 = vote_counts.iter().map(|(_, count)| count).sum();
+    if total == 0 || total < rules.quorum {
+        return VoteTally::NoQuorum;
+    }
+
+    let top = vote_counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let mut leaders = vote_counts.iter().filter(|(_, count)| *count == top);
+    let leader = match (leaders.next(), leaders.next()) {
+        (Some((outcome, _)), None) => *outcome,
+        _ => return VoteTally::Tie,
+    };
+
+    // The leading outcome needs `supermajority` basis points of the votes cast
+    if (top as u128) * 10000 < (rules.supermajority as u128) * (total as u128) {
+        return VoteTally::NoSupermajority;
+    }
+
+    VoteTally::Majority(leader)
+}
+
+/// Resolves a disputed market to `winning_outcome`: pays the resolution reward to the winning side
+/// and splits the losing bonds of every round. A void outcome refunds every matched bet instead of settling it.
+fn settle_dispute(
+    deps: &mut DepsMut,
+    env: &Env,
+    config: &Config,
+    market: &mut Market,
+    proposal: &mut ResolutionProposal,
+    dispute: &mut Dispute,
+    winning_outcome: u8,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new();
+    let mut rounds = load_dispute_rounds(deps.storage, market.id, proposal)?;
+    let votes: Vec<Vote> = rounds.iter().flat_map(|round| round.votes.iter().cloned()).collect();
+
+    market.status = MarketStatus::Resolved;
+    market.result = Some(winning_outcome);
+    proposal.status = ProposalStatus::Resolved;
+    dispute.status = DisputeStatus::Resolved;
+    dispute.appeal_deadline = None;
+    DISPUTES.save(deps.storage, market.id, dispute)?;
+
+    // A void result has no winning option, every matched bet is refunded instead of settled
+    if winning_outcome == VOID_OUTCOME {
+        refund_all_bets(deps, market.id)?;
+    }
+
+    // Determine the winner and send reward: the proposer if the proposal stands, otherwise the first
+    // challenger who proposed the result. A void result refunds the market, so the reward goes back to
+    // the creator who funded it. Any other result that nobody proposed sends it to the treasury.
+    let winner = if winning_outcome == proposal.proposed_result {
+        proposal.proposer.clone()
+    } else if winning_outcome == VOID_OUTCOME {
+        market.creator.clone()
+    } else {
+        rounds.iter()
+            .find(|round| round.proposed_outcome == winning_outcome)
+            .map_or_else(|| config.treasury.clone(), |round| round.challenger.clone())
+    };
+
+    response = response.add_message(CosmosMsg::Bank(BankMsg::Send {
+        to_address: winner.to_string(),
+        amount: vec![Coin {
+            denom: config.token_denom.clone(),
+            amount: market.resolution_reward,
+        }],
+    }));
+
+    // Slash the losing bonds: the proposer loses when the proposal is overturned and each round's
+    // challenger loses when the outcome they challenged stands. The winners' share is paid out
+    // pro rata to their bonds on redemption, the voters for the result of every round are credited
+    // and the treasury receives its share and any rounding dust.
+    let proposer_won = winning_outcome == proposal.proposed_result;
+    let round_won = |round: &DisputeRound| winning_outcome != round.challenged_outcome;
+    let (mut winning_bonds, mut slashed) = if proposer_won {
+        (proposal.bond_amount, Uint128::zero())
+    } else {
+        (Uint128::zero(), proposal.bond_amount)
+    };
+    for round in &rounds {
+        if round_won(round) {
+            winning_bonds += round.bond;
         } else {
-            dispute.challenger.clone()
+            slashed += round.bond;
+        }
+    }
+
+    let split = &config.bond_split;
+    let winners_share = slashed.multiply_ratio(split.winner, 10000u128);
+    let mut paid_winners = Uint128::zero();
+    proposal.winner_bond_share = if proposer_won {
+        winners_share.multiply_ratio(proposal.bond_amount, winning_bonds)
+    } else {
+        Uint128::zero()
+    };
+    paid_winners += proposal.winner_bond_share;
+    for round in rounds.iter_mut() {
+        if round_won(round) {
+            round.winner_bond_share = winners_share.multiply_ratio(round.bond, winning_bonds);
+            paid_winners += round.winner_bond_share;
+        }
+        DISPUTE_ROUNDS.save(deps.storage, (market.id, round.round), round)?;
+    }
+    // The voters for the result of any round share a reward pool, claimed pro rata to their weight
+    let mut winning_weight = 0u64;
+    for vote in votes.iter().filter(|vote| vote.option_id == winning_outcome) {
+        winning_weight = winning_weight.saturating_add(vote.weight);
+        VOTER_REWARD_WEIGHTS.update(deps.storage, (market.id, vote.voter.clone()), |weight| -> StdResult<u64> {
+            Ok(weight.unwrap_or(0).saturating_add(vote.weight))
+        })?;
+    }
+    let voter_reward_pool = if winning_weight == 0 {
+        Uint128::zero()
+    } else {
+        let pool = VoterRewardPool {
+            market_id: market.id,
+            winning_outcome,
+            bond_rewards: slashed.multiply_ratio(split.voters, 10000u128),
+            fee_rewards: Uint128::zero(),
+            fee_share: config.voter_fee_share,
+            winning_weight,
         };
+        VOTER_REWARD_POOLS.save(deps.storage, market.id, &pool)?;
+        pool.bond_rewards
+    };
+    let mut treasury_share = slashed - paid_winners - voter_reward_pool;
 
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: winner.to_string(),
+    // Fees are accrued after the pool exists so that the voters receive their share
+    accrue_resolution_fees(deps.storage, market)?;
+
+    // Slash the stake that voted against the result, it goes to the treasury with its bond share
+    let slashed_stake = if dispute.snapshot_height.is_some() && config.staking.slash_rate > 0 {
+        slash_losing_voters(deps.storage, env, config.staking.slash_rate, &votes, winning_outcome)?
+    } else {
+        Uint128::zero()
+    };
+    treasury_share += slashed_stake;
+    if !treasury_share.is_zero() {
+        response = response.add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: config.treasury.to_string(),
             amount: vec![Coin {
                 denom: config.token_denom.clone(),
-                amount: market.resolution_reward,
+                amount: treasury_share,
             }],
         }));
-
-        attributes.push(("result".to_string(), "dispute_resolved".to_string()));
-        attributes.push(("winner".to_string(), winner.to_string()));
-        attributes.push(("winning_outcome".to_string(), winning_outcome.to_string()));
-    } else {
-        return Err(ContractError::InvalidProposalState {});
     }
 
-    MARKETS.save(deps.storage, market_id, &market)?;
-    PROPOSALS.save(deps.storage, market_id, &proposal)?;
+    Ok(response
+        .add_attribute("result", "dispute_resolved")
+        .add_attribute("winner", winner.to_string())
+        .add_attribute("winning_outcome", winning_outcome.to_string())
+        .add_attribute("winner_bond_share", paid_winners.to_string())
+        .add_attribute("voter_reward_pool", voter_reward_pool.to_string())
+        .add_attribute("treasury_bond_share", treasury_share.to_string())
+        .add_attribute("slashed_stake", slashed_stake.to_string()))
+}
 
-    Ok(Response::new()
-        .add_messages(messages)
-        .add_attributes(attributes))
+/// Slashes `slash_rate` basis points of the voting weight of every vote against `winning_outcome`,
+/// from the voter's stake first and then from their unbonding tokens. Returns the slashed total.
+fn slash_losing_voters(storage: &mut dyn Storage, env: &Env, slash_rate: u64, votes: &[Vote], winning_outcome: u8) -> StdResult<Uint128> {
+    let mut total = Uint128::zero();
+
+    for vote in votes.iter().filter(|vote| vote.option_id != winning_outcome) {
+        let mut remaining = Uint128::from(vote.weight).multiply_ratio(slash_rate, 10000u128);
+
+        let stake = STAKES.may_load(storage, vote.voter.clone())?.unwrap_or_default();
+        let from_stake = std::cmp::min(stake, remaining);
+        if !from_stake.is_zero() {
+            STAKES.save(storage, vote.voter.clone(), &(stake - from_stake), env.block.height)?;
+            remaining -= from_stake;
+            total += from_stake;
+            TOTAL_STAKED.update(storage, |staked| -> StdResult<Uint128> { Ok(staked - from_stake) })?;
+        }
+
+        // Unstaking after voting does not escape the slash
+        if !remaining.is_zero() {
+            let mut unbonding = UNBONDING.may_load(storage, vote.voter.clone())?.unwrap_or_default();
+            for entry in unbonding.iter_mut() {
+                let from_entry = std::cmp::min(entry.amount, remaining);
+                entry.amount -= from_entry;
+                remaining -= from_entry;
+                total += from_entry;
+            }
+            unbonding.retain(|entry| !entry.amount.is_zero());
+            UNBONDING.save(storage, vote.voter.clone(), &unbonding)?;
+        }
+    }
+
+    Ok(total)
 }
 
 pub fn redeem_bond_amount(
@@ -1062,39 +2989,55 @@ pub fn redeem_bond_amount(
     market_id: u64,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Redemptions)?;
     let market = MARKETS.load(deps.storage, market_id)?;
-    let proposal = PROPOSALS.load(deps.storage, market_id)?;
+    let mut proposal = PROPOSALS.load(deps.storage, market_id)?;
 
     // Check if the market is resolved
     if market.status != MarketStatus::Resolved {
         return Err(ContractError::MarketNotResolved {});
     }
 
-    // Check if the caller is either the proposer or the challenger
+    // Check if the caller is either the proposer or the challenger of a round
     let is_proposer = info.sender == proposal.proposer;
-    let is_challenger = DISPUTES.may_load(deps.storage, market_id)?.map_or(false, |d| info.sender == d.challenger);
+    let rounds: Vec<DisputeRound> = load_dispute_rounds(deps.storage, market_id, &proposal)?
+        .into_iter()
+        .filter(|round| round.challenger == info.sender)
+        .collect();
 
-    if !is_proposer && !is_challenger {
+    if !is_proposer && rounds.is_empty() {
         return Err(ContractError::Unauthorized {});
     }
 
-    // Check if the caller is the correct proposer
-    let is_winner = if is_proposer {
-        market.result == Some(proposal.proposed_result)
-    } else {
-        market.result != Some(proposal.proposed_result)
-    };
+    // The proposer wins when the proposal stands, a challenger when the outcome they challenged was overturned
+    let proposer_won = is_proposer && market.result == Some(proposal.proposed_result);
+    let won_rounds: Vec<DisputeRound> = rounds.into_iter().filter(|round| market.result != Some(round.challenged_outcome)).collect();
 
-    if !is_winner {
+    if !proposer_won && won_rounds.is_empty() {
         return Err(ContractError::NotWinner {});
     }
 
-    // Send the bond amount to the winner
+    // Send every winning bond along with its share of the losing bonds
+    let mut amount = Uint128::zero();
+    if proposer_won && !proposal.bond_redeemed {
+        amount += proposal.bond_amount + proposal.winner_bond_share;
+        proposal.bond_redeemed = true;
+        PROPOSALS.save(deps.storage, market_id, &proposal)?;
+    }
+    for mut round in won_rounds.into_iter().filter(|round| !round.bond_redeemed) {
+        amount += round.bond + round.winner_bond_share;
+        round.bond_redeemed = true;
+        DISPUTE_ROUNDS.save(deps.storage, (market_id, round.round), &round)?;
+    }
+
+    if amount.is_zero() {
+        return Err(ContractError::BondAlreadyRedeemed {});
+    }
     let msg = CosmosMsg::Bank(BankMsg::Send {
         to_address: info.sender.to_string(),
         amount: vec![Coin {
             denom: config.token_denom,
-            amount: market.resolution_bond,
+            amount,
         }],
     });
 
@@ -1104,13 +3047,211 @@ pub fn redeem_bond_amount(
             ("method".to_string(), "redeem_bond_amount".to_string()),
             ("market_id".to_string(), market_id.to_string()),
             ("recipient".to_string(), info.sender.to_string()),
-            ("amount".to_string(), market.resolution_bond.to_string()),
+            ("amount".to_string(), amount.to_string()),
         ]))
 }
 
+pub fn claim_voter_reward(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(&config, PauseScope::Redemptions)?;
+
+    let claimable = voter_reward(deps.as_ref(), market_id, &info.sender)?.claimable;
+    if claimable.is_zero() {
+        return Err(ContractError::NoVoterReward {});
+    }
+    VOTER_REWARDS_CLAIMED.update(deps.storage, (market_id, info.sender.clone()), |claimed| -> StdResult<Uint128> {
+        Ok(claimed.unwrap_or_default() + claimable)
+    })?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.token_denom,
+                amount: claimable,
+            }],
+        }))
+        .add_attribute("method", "claim_voter_reward")
+        .add_attribute("market_id", market_id.to_string())
+        .add_attribute("voter", info.sender.to_string())
+        .add_attribute("amount", claimable.to_string()))
+}
+
+/// The voter's share of the pool grows with the fees accrued after resolution, less what they already claimed
+fn voter_reward(deps: Deps, market_id: u64, voter: &Addr) -> StdResult<VoterRewardResponse> {
+    let pool = VOTER_REWARD_POOLS.may_load(deps.storage, market_id)?;
+    let weight = VOTER_REWARD_WEIGHTS.may_load(deps.storage, (market_id, voter.clone()))?.unwrap_or(0);
+    let claimed = VOTER_REWARDS_CLAIMED.may_load(deps.storage, (market_id, voter.clone()))?.unwrap_or_default();
+    let earned = pool.as_ref().map_or(Uint128::zero(), |pool| pool.total().multiply_ratio(weight, pool.winning_weight));
+
+    Ok(VoterRewardResponse {
+        market_id,
+        voter: voter.clone(),
+        claimable: earned.saturating_sub(claimed),
+        claimed,
+        pool,
+    })
+}
+
 #[entry_point]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let new_version: Version = CONTRACT_VERSION.parse().map_err(|_| ContractError::InvalidVersion { version: CONTRACT_VERSION.to_string() })?;
+
+    // v0.1.0 never stored a cw2 version, so its absence means the legacy layout
+    let stored_version = match cw2::CONTRACT.may_load(deps.storage)? {
+        Some(info) => {
+            if info.contract != CONTRACT_NAME {
+                return Err(ContractError::InvalidContractName { contract: info.contract });
+            }
+            info.version.parse().map_err(|_| ContractError::InvalidVersion { version: info.version })?
+        }
+        None => Version::new(0, 1, 0),
+    };
+
+    // Refuse downgrades, the stored state may not be readable by older code
+    if stored_version > new_version {
+        return Err(ContractError::CannotDowngrade { from: stored_version.to_string(), to: new_version.to_string() });
+    }
+
+    let mut response = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", new_version.to_string());
+
+    if stored_version < Version::new(0, 2, 0) {
+        let migrated = migrate_from_v0_1(&mut deps)?;
+        response = response.add_attribute("migrated_orders", migrated.to_string());
+    }
+
+    if let Some(update) = msg.config {
+        let mut config = CONFIG.load(deps.storage)?;
+        let attributes = apply_config_update(deps.storage, deps.api, &mut config, update)?;
+        CONFIG.save(deps.storage, &config)?;
+        response = response.add_attributes(attributes);
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(response)
+}
+
+/// Rewrites the v0.1.0 `Config`, `Market` and `Order` layouts and backfills the indexes,
+/// order book and positions that later versions maintain as bets are placed.
+/// Returns the number of migrated orders.
+fn migrate_from_v0_1(deps: &mut DepsMut) -> Result<usize, ContractError> {
+    let legacy_config = LEGACY_CONFIG.load(deps.storage)?;
+    CONFIG.save(deps.storage, &Config {
+        admin: legacy_config.admin,
+        token_denom: legacy_config.token_denom,
+        platform_fee: legacy_config.platform_fee,
+        treasury: legacy_config.treasury,
+        challenging_period: legacy_config.challenging_period,
+        voting_period: legacy_config.voting_period,
+        min_bet: legacy_config.min_bet,
+        whitelist_enabled: legacy_config.whitelist_enabled,
+        guardian: None,
+        paused: PauseFlags::default(),
+        bond_split: BondSplit::default(),
+        dispute_rules: DisputeRules::default(),
+        vote_mode: VoteMode::Open,
+        staking: StakingConfig::default(),
+        escalation: EscalationConfig::default(),
+        voter_fee_share: 0,
+    })?;
+    TOTAL_STAKED.save(deps.storage, &Uint128::zero())?;
+
+    let legacy_markets = LEGACY_MARKETS
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (market_id, legacy) in legacy_markets {
+        // Canceled markets pay nothing out, so no fee can be withheld
+        let platform_fee = if legacy.status == MarketStatus::Canceled { Uint128::zero() } else { legacy_config.platform_fee };
+        MARKETS.save(deps.storage, market_id, &Market {
+            id: legacy.id,
+            creator: legacy.creator,
+            question: legacy.question,
+            description: legacy.description,
+            option_statuses: vec![OptionStatus::Active; legacy.options.len()],
+            options: legacy.options,
+            category: legacy.category,
+            start_time: legacy.start_time,
+            end_time: legacy.end_time,
+            status: legacy.status,
+            resolution_bond: legacy.resolution_bond,
+            resolution_reward: legacy.resolution_reward,
+            result: legacy.result,
+            opening_auction: false,
+            platform_fee,
+        })?;
+    }
+
+    // The per-user lists of v0.1.0 share their namespaces with the new indexes and are rebuilt below
+    let user_orders = LEGACY_USER_ORDERS.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+    for key in user_orders {
+        LEGACY_USER_ORDERS.remove(deps.storage, key);
+    }
+    let user_matched_bets = LEGACY_USER_MATCHED_BETS.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+    for key in user_matched_bets {
+        LEGACY_USER_MATCHED_BETS.remove(deps.storage, key);
+    }
+
+    // Legacy orders are removed before saving, since the indexed map reads the previous value
+    let legacy_orders = LEGACY_ORDERS
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let migrated = legacy_orders.len();
+    for (order_id, legacy) in legacy_orders {
+        LEGACY_ORDERS.remove(deps.storage, order_id);
+        let order = Order {
+            id: legacy.id,
+            market_id: legacy.market_id,
+            creator: legacy.creator,
+            option_id: legacy.option_id,
+            side: legacy.side,
+            amount: legacy.amount,
+            odds: legacy.odds,
+            filled_amount: legacy.filled_amount,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            time_in_force: TimeInForce::GoodTilCancelled,
+            keep_on_suspend: false,
+        };
+        save_order(deps.storage, &order)?;
+        USER_ORDERS.save(deps.storage, (order.creator.clone(), order.id), &order.market_id)?;
+    }
+
+    // Matched bets kept their layout, saving them again builds the market index
+    let matched_bets = MATCHED_BETS
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (matched_bet_id, matched_bet) in matched_bets {
+        MATCHED_BETS.save(deps.storage, matched_bet_id, &matched_bet)?;
+        LAST_MATCHED_ODDS.save(deps.storage, (matched_bet.market_id, matched_bet.option_id), &matched_bet.odds)?;
+        USER_MATCHED_BETS.save(deps.storage, (matched_bet.back_user.clone(), matched_bet_id), &matched_bet.market_id)?;
+        USER_MATCHED_BETS.save(deps.storage, (matched_bet.lay_user.clone(), matched_bet_id), &matched_bet.market_id)?;
+
+        // Positions only matter until settlement, bets of settled markets pay out in full
+        let market = MARKETS.load(deps.storage, matched_bet.market_id)?;
+        if matches!(market.status, MarketStatus::Active | MarketStatus::Closed | MarketStatus::InDispute) {
+            update_positions(deps.storage, &matched_bet, market.platform_fee)?;
+        }
+    }
+
+    // Markets resolved under v0.1.0 accrue the fees of their unredeemed bets, as if they resolved now
+    let resolved_markets = MARKETS
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .filter(|item| matches!(item, Ok((_, market)) if market.status == MarketStatus::Resolved))
+        .map(|item| item.map(|(_, market)| market))
+        .collect::<StdResult<Vec<Market>>>()?;
+    for market in resolved_markets {
+        accrue_resolution_fees(deps.storage, &market)?;
+    }
+
+    Ok(migrated)
 }
 
 
@@ -1122,42 +3263,102 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { field, value } => update_config(deps, info, field, value),
-        ExecuteMsg::CreateMarket { category, question, description, options, start_time, end_time, resolution_bond, resolution_reward } => 
-            create_market(deps, env, info, category, question, description, options, start_time, end_time, resolution_bond, resolution_reward),
+        ExecuteMsg::UpdateConfig(update) => update_config(deps, info, update),
+        ExecuteMsg::ProposeNewAdmin { new_admin, expires_at } => propose_new_admin(deps, env, info, new_admin, expires_at),
+        ExecuteMsg::AcceptAdmin {} => accept_admin(deps, env, info),
+        ExecuteMsg::CancelAdminTransfer {} => cancel_admin_transfer(deps, info),
+        ExecuteMsg::Pause { scope } => set_paused(deps, info, scope, true),
+        ExecuteMsg::Unpause { scope } => set_paused(deps, info, scope, false),
+        ExecuteMsg::GrantRole { role, address } => grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => revoke_role(deps, info, role, address),
+        ExecuteMsg::CreateMarket { category, question, description, options, start_time, end_time, resolution_bond, resolution_reward, opening_auction } => 
+            create_market(deps, env, info, CreateMarketParams {
+                category,
+                question,
+                description,
+                options,
+                start_time,
+                end_time,
+                resolution_bond,
+                resolution_reward,
+                opening_auction: opening_auction.unwrap_or(false),
+            }),
         ExecuteMsg::CancelMarket { market_id } => cancel_market(deps, info, market_id),
         ExecuteMsg::CloseMarket { market_id } => close_market(deps, env, info, market_id),
+        ExecuteMsg::OpenMarket { market_id } => open_market(deps, env, market_id),
+        ExecuteMsg::SuspendMarket { market_id } => suspend_market(deps, info, market_id),
+        ExecuteMsg::ResumeMarket { market_id } => resume_market(deps, info, market_id),
+        ExecuteMsg::SuspendOption { market_id, option_id } => suspend_option(deps, info, market_id, option_id),
+        ExecuteMsg::ResumeOption { market_id, option_id } => resume_option(deps, env, info, market_id, option_id),
+        ExecuteMsg::VoidOption { market_id, option_id } => void_option(deps, info, market_id, option_id),
         ExecuteMsg::ProposeResult { market_id, winning_outcome } => propose_market_result(deps, env, info, market_id, winning_outcome),
-        ExecuteMsg::PlaceOrder { market_id, option_id, order_type, side, amount, odds } => 
-            place_order(deps, env, info, market_id, option_id, order_type, side, amount, odds),
-        ExecuteMsg::CancelOrder { order_id } => cancel_order(deps, info, order_id),
+        ExecuteMsg::PlaceOrder { market_id, option_id, order_type, side, amount, odds, time_in_force, keep_on_suspend } => 
+            place_order(deps, env, info, OrderParams {
+                market_id,
+                option_id,
+                order_type,
+                side,
+                amount,
+                odds,
+                time_in_force,
+                keep_on_suspend: keep_on_suspend.unwrap_or(false),
+            }),
+        ExecuteMsg::CancelOrder { order_id } => cancel_order(deps, env, info, order_id),
         ExecuteMsg::RedeemWinnings { matched_bet_id } => redeem_winnings(deps, env, info, matched_bet_id),
+        ExecuteMsg::RedeemMarket { market_id } => redeem_market(deps, info, market_id),
+        ExecuteMsg::RedeemAll { start_after, limit } => redeem_all(deps, info, start_after, limit),
         ExecuteMsg::AddToWhitelist { address } => add_to_whitelist(deps, info, address),
         ExecuteMsg::RemoveFromWhitelist { address } => remove_from_whitelist(deps, info, address),
         ExecuteMsg::RaiseDispute { market_id, proposed_outcome, evidence } => 
             raise_dispute(deps, env, info, market_id, proposed_outcome, evidence),
         ExecuteMsg::CastVote { market_id, outcome } => cast_vote(deps, env, info, market_id, outcome),
+        ExecuteMsg::CommitVote { market_id, hash } => commit_vote(deps, env, info, market_id, hash),
+        ExecuteMsg::RevealVote { market_id, outcome, salt } => reveal_vote(deps, env, info, market_id, outcome, salt),
         ExecuteMsg::ResolveDispute { market_id } => resolve_dispute(deps, env, info, market_id),
+        ExecuteMsg::ClaimVoterReward { market_id } => claim_voter_reward(deps, info, market_id),
+        ExecuteMsg::AppealDispute { market_id, proposed_outcome, evidence } => appeal_dispute(deps, env, info, market_id, proposed_outcome, evidence),
+        ExecuteMsg::ResolveEscalatedDispute { market_id, outcome } => resolve_escalated_dispute(deps, env, info, market_id, outcome),
         ExecuteMsg::RedeemBondAmount { market_id } => redeem_bond_amount(deps, env, info, market_id), // Fix Bug ID #2
+        ExecuteMsg::SweepFees { market_id } => sweep_fees(deps, info, market_id),
+        ExecuteMsg::CashOut { market_id, option_id, worst_odds } => cash_out(deps, env, info, market_id, option_id, worst_odds),
+        ExecuteMsg::Stake {} => stake(deps, env, info),
+        ExecuteMsg::Unstake { amount } => unstake(deps, env, info, amount),
+        ExecuteMsg::ClaimUnstaked {} => claim_unstaked(deps, env, info),
+        ExecuteMsg::Deposit {} => deposit(deps, info),
+        ExecuteMsg::Withdraw { amount } => withdraw(deps, info, amount),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::PendingAdmin {} => to_json_binary(&query_pending_admin(deps)?),
+        QueryMsg::HasRole { role, address } => to_json_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::Roles { address } => to_json_binary(&query_roles(deps, address)?),
+        QueryMsg::RoleMembers { role, start_after, limit } => to_json_binary(&query_role_members(deps, role, start_after, limit)?),
         QueryMsg::Market { market_id } => to_json_binary(&query_market(deps, market_id)?),
         QueryMsg::Markets { status, start_after, limit } => to_json_binary(&query_markets(deps, status, start_after, limit)?),
         QueryMsg::Order { order_id } => to_json_binary(&query_order(deps, order_id)?),
-        QueryMsg::UserOrders { user, market_id, start_after, limit } => to_json_binary(&query_user_orders(deps, user, market_id, start_after, limit)?),
-        QueryMsg::MarketOrders { market_id, side, start_after, limit } => to_json_binary(&query_market_orders(deps, market_id, side, start_after, limit)?),
+        QueryMsg::UserOrders { user, market_id, status, start_after, limit } => to_json_binary(&query_user_orders(deps, user, market_id, status, start_after, limit)?),
+        QueryMsg::MarketOrders { market_id, side, start_after, limit } => to_json_binary(&query_market_orders(deps, env, market_id, side, start_after, limit)?),
         QueryMsg::MatchedBets { market_id, user, start_after, limit } => to_json_binary(&query_matched_bets(deps, market_id, user, start_after, limit)?),
         QueryMsg::ResolutionProposal { market_id } => to_json_binary(&query_resolution_proposal(deps, market_id)?),
         QueryMsg::Dispute { market_id } => to_json_binary(&query_dispute(deps, market_id)?),
-        QueryMsg::Votes { market_id } => to_json_binary(&query_votes(deps, market_id)?),
+        QueryMsg::DisputeRounds { market_id } => to_json_binary(&query_dispute_rounds(deps, market_id)?),
+        QueryMsg::VoterReward { market_id, voter } => to_json_binary(&voter_reward(deps, market_id, &voter)?),
+        QueryMsg::Votes { market_id } => to_json_binary(&query_revealed_votes(deps, &env, market_id)?),
         QueryMsg::IsWhitelisted { user } => to_json_binary(&query_is_whitelisted(deps, user)?),
         QueryMsg::MarketStatistics { market_id } => to_json_binary(&query_market_statistics(deps, market_id)?),
         QueryMsg::WhitelistedAddresses { start_after, limit } => to_json_binary(&query_whitelisted_addresses(deps, start_after, limit)?),
+        QueryMsg::MarketFees { market_id } => to_json_binary(&query_market_fees(deps, market_id)?),
+        QueryMsg::OrderBook { market_id, option_id, depth } => to_json_binary(&query_order_book(deps, env, market_id, option_id, depth)?),
+        QueryMsg::Balance { user } => to_json_binary(&query_balance(deps, user)?),
+        QueryMsg::Stake { address } => to_json_binary(&query_stake(deps, address)?),
+        QueryMsg::VotingPower { market_id, address } => to_json_binary(&query_voting_power(deps, market_id, address)?),
+        QueryMsg::Position { user, market_id, option_id } => to_json_binary(&query_position(deps, user, market_id, option_id)?),
+        QueryMsg::Positions { user, market_id } => to_json_binary(&query_positions(deps, user, market_id)?),
+        QueryMsg::CashOutQuote { user, market_id, option_id } => to_json_binary(&query_cash_out_quote(deps, &env, user, market_id, option_id)?),
     }
 }
 
@@ -1165,6 +3366,34 @@ fn query_config(deps: Deps) -> StdResult<Config> {
     CONFIG.load(deps.storage)
 }
 
+fn query_pending_admin(deps: Deps) -> StdResult<Option<PendingAdmin>> {
+    PENDING_ADMIN.may_load(deps.storage)
+}
+
+fn query_has_role(deps: Deps, role: Role, address: Addr) -> StdResult<bool> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(has_role(deps.storage, &config, &address, role))
+}
+
+fn query_roles(deps: Deps, address: Addr) -> StdResult<Vec<Role>> {
+    Ok(Role::ALL
+        .into_iter()
+        .filter(|role| ROLES.has(deps.storage, (role.key(), address.clone())))
+        .collect())
+}
+
+fn query_role_members(deps: Deps, role: Role, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<String>> {
+    let start = start_after.map(Addr::unchecked);
+    let limit = limit.unwrap_or(30) as usize;
+
+    ROLES
+        .prefix(role.key())
+        .keys(deps.storage, start.map(Bound::exclusive), None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|addr| addr.map(|addr| addr.to_string()))
+        .collect()
+}
+
 fn query_market(deps: Deps, market_id: u64) -> StdResult<Market> {
     MARKETS.load(deps.storage, market_id)
 }
@@ -1195,21 +3424,23 @@ fn query_order(deps: Deps, order_id: u64) -> StdResult<Order> {
     ORDERS.load(deps.storage, order_id)
 }
 
-fn query_user_orders(deps: Deps, user: Addr, market_id: Option<u64>, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<Order>> {
+fn query_user_orders(deps: Deps, user: Addr, market_id: Option<u64>, status: Option<String>, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<Order>> {
     let limit = limit.unwrap_or(30) as usize;
     let start = start_after.map(Bound::exclusive);
-    
-    ORDERS
+
+    USER_ORDERS
+        .prefix(user)
         .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
-        .filter(|r| {
-            if let Ok((_, order)) = r {
-                order.creator == user && market_id.map_or(true, |id| order.market_id == id)
-            } else {
-                false
-            }
+        .filter(|r| match r {
+            Ok((_, order_market_id)) => market_id.is_none_or(|id| *order_market_id == id),
+            Err(_) => true,
+        })
+        .map(|item| item.and_then(|(order_id, _)| ORDERS.load(deps.storage, order_id)))
+        .filter(|r| match r {
+            Ok(order) => status.as_ref().is_none_or(|s| order.status.to_string() == *s),
+            Err(_) => true,
         })
         .take(limit)
-        .map(|item| item.map(|(_, order)| order))
         .collect()
 }
 
@@ -1220,23 +3451,20 @@ fn query_is_whitelisted(deps: Deps, user: Addr) -> StdResult<bool> {
 
 fn query_market_statistics(deps: Deps, market_id: u64) -> StdResult<MarketStatistics> {
     let market = MARKETS.load(deps.storage, market_id)?;
-    
-    let total_volume: Uint128 = MATCHED_BETS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|r| {
-            r.ok().and_then(|(_, matched_bet)| {
-                if matched_bet.market_id == market_id {
-                    Some(matched_bet.amount)
-                } else {
-                    None
-                }
-            })
-        })
-        .sum();
+    
+    let total_volume: Uint128 = MATCHED_BETS
+        .idx
+        .market
+        .prefix(market_id)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|r| r.map(|(_, matched_bet)| matched_bet.amount))
+        .sum::<StdResult<Uint128>>()?;
 
     let order_count = ORDERS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter(|r| r.as_ref().map(|(_, order)| order.market_id == market_id).unwrap_or(false))
+        .idx
+        .market
+        .prefix(market_id)
+        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
         .count();
 
     Ok(MarketStatistics {
@@ -1249,29 +3477,47 @@ fn query_market_statistics(deps: Deps, market_id: u64) -> StdResult<MarketStatis
 
 pub fn query_market_orders(
     deps: Deps,
+    env: Env,
     market_id: u64,
     side: Option<String>,
     start_after: Option<u64>,
     limit: Option<u32>
 ) -> StdResult<Vec<Order>> {
     let limit = limit.unwrap_or(30) as usize;
-    let start = start_after.map(Bound::exclusive);
+    let market = MARKETS.load(deps.storage, market_id)?;
+    let sides: Vec<OrderSide> = [OrderSide::Back, OrderSide::Lay]
+        .into_iter()
+        .filter(|order_side| side.as_ref().is_none_or(|s| order_side.to_string() == *s))
+        .collect();
 
-    ORDERS
-        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
-        .filter(|r| {
-            if let Ok((_, order)) = r {
-                order.market_id == market_id && 
-                side.as_ref().map_or(true, |s| order.side.to_string() == *s) &&
-                (order.status == OrderStatus::Open || order.status == OrderStatus::PartiallyFilled) &&
-                order.amount > order.filled_amount
-            } else {
-                false
+    // Only orders resting on the book are read, in order id order for paging
+    let mut order_ids = Vec::new();
+    for option_id in 0..market.options.len() as u8 {
+        for order_side in &sides {
+            for key in ORDER_BOOK
+                .prefix((market_id, (option_id, order_side.key())))
+                .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            {
+                let (_, order_id) = key?;
+                if start_after.is_none_or(|start| order_id > start) {
+                    order_ids.push(order_id);
+                }
             }
-        })
-        .take(limit)
-        .map(|item| item.map(|(_, order)| order))
-        .collect()
+        }
+    }
+    order_ids.sort_unstable();
+
+    let mut orders = Vec::new();
+    for order_id in order_ids {
+        if orders.len() == limit {
+            break;
+        }
+        let order = ORDERS.load(deps.storage, order_id)?;
+        if order.amount > order.filled_amount && !order.is_expired(env.block.time.seconds()) {
+            orders.push(order);
+        }
+    }
+    Ok(orders)
 }
 
 pub fn query_matched_bets(
@@ -1284,19 +3530,27 @@ pub fn query_matched_bets(
     let limit = limit.unwrap_or(30) as usize;
     let start = start_after.map(Bound::exclusive);
 
-    MATCHED_BETS
-        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
-        .filter(|r| {
-            if let Ok((_, matched_bet)) = r {
-                market_id.map_or(true, |id| matched_bet.market_id == id) &&
-                user.as_ref().map_or(true, |addr| matched_bet.back_user == *addr || matched_bet.lay_user == *addr)
-            } else {
-                false
-            }
-        })
-        .take(limit)
-        .map(|item| item.map(|(_, matched_bet)| matched_bet))
-        .collect()
+    // A user's bets are read from their own index rather than the whole market
+    if let Some(user) = user {
+        return USER_MATCHED_BETS
+            .prefix(user)
+            .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+            .filter(|r| match r {
+                Ok((_, bet_market_id)) => market_id.is_none_or(|id| *bet_market_id == id),
+                Err(_) => true,
+            })
+            .take(limit)
+            .map(|item| item.and_then(|(matched_bet_id, _)| MATCHED_BETS.load(deps.storage, matched_bet_id)))
+            .collect();
+    }
+
+    match market_id {
+        Some(market_id) => MATCHED_BETS.idx.market.prefix(market_id).range(deps.storage, start, None, cosmwasm_std::Order::Ascending),
+        None => MATCHED_BETS.range(deps.storage, start, None, cosmwasm_std::Order::Ascending),
+    }
+    .take(limit)
+    .map(|item| item.map(|(_, matched_bet)| matched_bet))
+    .collect()
 }
 
 pub fn query_resolution_proposal(deps: Deps, market_id: u64) -> StdResult<Option<ResolutionProposal>> {
@@ -1307,101 +3561,1182 @@ pub fn query_dispute(deps: Deps, market_id: u64) -> StdResult<Option<Dispute>> {
     DISPUTES.may_load(deps.storage, market_id)
 }
 
-pub fn query_votes(deps: Deps, market_id: u64) -> StdResult<(Vec<Vote>, Vec<(u8, u64)>)> {
+fn query_dispute_rounds(deps: Deps, market_id: u64) -> StdResult<Vec<DisputeRound>> {
+    match PROPOSALS.may_load(deps.storage, market_id)? {
+        Some(proposal) => load_dispute_rounds(deps.storage, market_id, &proposal),
+        None => Ok(vec![]),
+    }
+}
+
+fn query_revealed_votes(deps: Deps, env: &Env, market_id: u64) -> StdResult<VotesResponse> {
+    // Revealed votes stay hidden until nobody can reveal anymore, so they cannot steer later reveals
+    let dispute = DISPUTES.may_load(deps.storage, market_id)?;
+    if dispute.and_then(|dispute| dispute.reveal_deadline).is_some_and(|deadline| env.block.time.seconds() <= deadline) {
+        return Err(StdError::generic_err("Votes are hidden until the reveal period ends"));
+    }
+    query_votes(deps, market_id)
+}
+
+pub fn query_votes(deps: Deps, market_id: u64) -> StdResult<VotesResponse> {
+    load_votes(deps.storage, market_id)
+}
+
+fn load_votes(storage: &dyn Storage, market_id: u64) -> StdResult<VotesResponse> {
     let votes: Vec<Vote> = VOTES
         .prefix(market_id)
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .range(storage, None, None, cosmwasm_std::Order::Ascending)
         .map(|item| item.map(|(_, vote)| vote))
         .collect::<StdResult<Vec<Vote>>>()?;
 
-    // Fix Bug ID #22: Remove unnecessary identity mapping
-    let vote_counts: Vec<(u8, u64)> = VOTE_COUNTS
-        .prefix(market_id)
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .collect::<StdResult<Vec<(u8, u64)>>>()?;
+    // Fix Bug ID #22: Remove unnecessary identity mapping
+    let vote_counts: Vec<(u8, u64)> = VOTE_COUNTS
+        .prefix(market_id)
+        .range(storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<(u8, u64)>>>()?;
+
+    Ok((votes, vote_counts))
+}
+
+pub fn query_order_book(deps: Deps, env: Env, market_id: u64, option_id: u8, depth: Option<u32>) -> StdResult<OrderBook> {
+    MARKETS.load(deps.storage, market_id)?;
+    let depth = depth.unwrap_or(10) as usize;
+
+    let back = query_price_levels(deps, &env, market_id, option_id, OrderSide::Back, depth)?;
+    let lay = query_price_levels(deps, &env, market_id, option_id, OrderSide::Lay, depth)?;
+
+    Ok(OrderBook {
+        market_id,
+        option_id,
+        best_back: back.first().map(|level| level.odds),
+        best_lay: lay.first().map(|level| level.odds),
+        back,
+        lay,
+        last_matched_odds: LAST_MATCHED_ODDS.may_load(deps.storage, (market_id, option_id))?,
+    })
+}
+
+/// Aggregates the resting orders of one side of the book into at most `depth` price levels
+fn query_price_levels(deps: Deps, env: &Env, market_id: u64, option_id: u8, side: OrderSide, depth: usize) -> StdResult<Vec<PriceLevel>> {
+    let mut levels: Vec<PriceLevel> = Vec::new();
+
+    for key in ORDER_BOOK
+        .prefix((market_id, (option_id, side.key())))
+        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+    {
+        let (rank, order_id) = key?;
+        let order = ORDERS.load(deps.storage, order_id)?;
+        if order.is_expired(env.block.time.seconds()) {
+            continue;
+        }
+
+        let odds = side.odds_from_rank(rank);
+        match levels.last_mut() {
+            Some(level) if level.odds == odds => {
+                level.amount += order.amount - order.filled_amount;
+                level.order_count += 1;
+            }
+            _ => {
+                if levels.len() == depth {
+                    break;
+                }
+                levels.push(PriceLevel {
+                    odds,
+                    amount: order.amount - order.filled_amount,
+                    order_count: 1,
+                });
+            }
+        }
+    }
+
+    Ok(levels)
+}
+
+fn query_stake(deps: Deps, address: Addr) -> StdResult<StakeResponse> {
+    Ok(StakeResponse {
+        staked: STAKES.may_load(deps.storage, address.clone())?.unwrap_or_default(),
+        unbonding: UNBONDING.may_load(deps.storage, address.clone())?.unwrap_or_default(),
+        total_staked: TOTAL_STAKED.may_load(deps.storage)?.unwrap_or_default(),
+        address,
+    })
+}
+
+fn query_voting_power(deps: Deps, market_id: u64, address: Addr) -> StdResult<u64> {
+    let dispute = DISPUTES.load(deps.storage, market_id)?;
+    // Addresses that cannot vote have no voting power
+    Ok(voting_power(deps.storage, &dispute, &address).unwrap_or(0))
+}
+
+fn query_balance(deps: Deps, user: Addr) -> StdResult<Uint128> {
+    Ok(BALANCES.may_load(deps.storage, user)?.unwrap_or_default())
+}
+
+fn query_position(deps: Deps, user: Addr, market_id: u64, option_id: u8) -> StdResult<PositionResponse> {
+    let market = MARKETS.load(deps.storage, market_id)?;
+    let position = POSITIONS.may_load(deps.storage, (user.clone(), market_id, option_id))?
+        .unwrap_or_else(|| Position::new(user, market_id, option_id));
+
+    Ok(PositionResponse {
+        pnl_if_win: position.pnl_if_win(market.platform_fee),
+        pnl_if_lose: position.pnl_if_lose(market.platform_fee),
+        position,
+    })
+}
+
+fn query_positions(deps: Deps, user: Addr, market_id: u64) -> StdResult<PositionsResponse> {
+    let market = MARKETS.load(deps.storage, market_id)?;
+
+    let positions: Vec<Position> = POSITIONS
+        .prefix((user, market_id))
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(_, position)| position))
+        .collect::<StdResult<Vec<Position>>>()?;
+
+    // Each outcome wins the position on its own option and loses every other one
+    let outcome_pnl = (0..market.options.len() as u8)
+        .map(|outcome| {
+            positions.iter().fold(Int128::zero(), |pnl, position| {
+                if position.option_id == outcome {
+                    pnl + position.pnl_if_win(market.platform_fee)
+                } else {
+                    pnl + position.pnl_if_lose(market.platform_fee)
+                }
+            })
+        })
+        .collect();
+
+    Ok(PositionsResponse {
+        market_id,
+        positions: positions
+            .into_iter()
+            .map(|position| PositionResponse {
+                pnl_if_win: position.pnl_if_win(market.platform_fee),
+                pnl_if_lose: position.pnl_if_lose(market.platform_fee),
+                position,
+            })
+            .collect(),
+        outcome_pnl,
+    })
+}
+
+/// Sizes the hedge that equalises the user's profit on the option whether it wins or loses,
+/// priced at the best odds currently on the opposite side of the book
+pub fn query_cash_out_quote(deps: Deps, env: &Env, user: Addr, market_id: u64, option_id: u8) -> StdResult<CashOutQuote> {
+    let position = POSITIONS.may_load(deps.storage, (user.clone(), market_id, option_id))?
+        .unwrap_or_else(|| Position::new(user, market_id, option_id));
+
+    // Profit before fees if the option wins and if it loses
+    let collateral = position.collateral().u128() as i128;
+    let profit_if_win = position.back_payout.u128() as i128 - collateral;
+    let profit_if_lose = (position.lay_stake + position.lay_liability).u128() as i128 - collateral;
+
+    let mut quote = CashOutQuote {
+        market_id,
+        option_id,
+        side: None,
+        amount: Uint128::zero(),
+        odds: 0,
+        available: Uint128::zero(),
+        profit: Int128::new(profit_if_win.min(profit_if_lose)),
+    };
+
+    // Long the option: lay it against resting backs, short the option: back it against resting lays
+    let (side, book_side) = match profit_if_win.cmp(&profit_if_lose) {
+        std::cmp::Ordering::Greater => (OrderSide::Lay, OrderSide::Back),
+        std::cmp::Ordering::Less => (OrderSide::Back, OrderSide::Lay),
+        std::cmp::Ordering::Equal => return Ok(quote),
+    };
+    let best_level = match query_price_levels(deps, env, market_id, option_id, book_side, 1)?.pop() {
+        Some(level) => level,
+        None => return Ok(quote),
+    };
+
+    // A stake `a` at odds `p` moves the difference between both outcomes by `a * p`
+    let difference = profit_if_win.abs_diff(profit_if_lose);
+    let amount = Uint128::new(difference).multiply_ratio(100u128, best_level.odds);
+    let liability = amount.multiply_ratio(best_level.odds - 100, 100u128);
+    let profit = match side {
+        OrderSide::Lay => (profit_if_win - liability.u128() as i128).min(profit_if_lose + amount.u128() as i128),
+        OrderSide::Back => (profit_if_win + liability.u128() as i128).min(profit_if_lose - amount.u128() as i128),
+    };
+
+    quote.side = Some(side);
+    quote.amount = amount;
+    quote.odds = best_level.odds;
+    quote.available = best_level.amount;
+    quote.profit = Int128::new(profit);
+    Ok(quote)
+}
+
+fn query_market_fees(deps: Deps, market_id: u64) -> StdResult<MarketFees> {
+    Ok(MARKET_FEES.may_load(deps.storage, market_id)?.unwrap_or(MarketFees {
+        market_id,
+        collected: Uint128::zero(),
+        pending: Uint128::zero(),
+    }))
+}
+
+fn query_whitelisted_addresses(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<String>> {
+    let start = start_after.map(|s| Addr::unchecked(s));
+    let limit = limit.unwrap_or(30) as usize;
+
+    let addresses: Vec<String> = WHITELISTED_ADDRESSES
+        .range(deps.storage, start.map(Bound::exclusive), None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .filter_map(|item| item.ok().map(|(addr, _)| addr.to_string()))
+        .collect();
+
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+    use cosmwasm_std::{Addr, Uint128, Coin, DepsMut, from_json, MemoryStorage, OwnedDeps};
+    use cosmwasm_std::Timestamp;
+    use crate::state::{FallbackRule, LegacyConfig, LegacyMarket, LegacyOrder};
+
+    const ADMIN: &str = "admin";
+    const USER1: &str = "user1";
+    const USER2: &str = "user2";
+    const USER3: &str = "user3";
+    const GUARDIAN: &str = "guardian";
+    const TOKEN_DENOM: &str = "utoken";
+
+    fn setup_contract(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            admin: Addr::unchecked(ADMIN),
+            token_denom: TOKEN_DENOM.to_string(),
+            platform_fee: Uint128::new(100),  // 1%
+            treasury: Addr::unchecked("treasury"),
+            challenging_period: 86400,  // 1 day
+            voting_period: 86400,  // 1 day
+            min_bet: Uint128::new(1000),
+            whitelist_enabled: false,
+            guardian: Some(Addr::unchecked(GUARDIAN)),
+            bond_split: None,
+            dispute_rules: None,
+            vote_mode: None,
+            staking: None,
+            escalation: None,
+            voter_fee_share: None,
+        };
+        let info = mock_info(ADMIN, &[]);
+        let _ = instantiate(deps, mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_instantiation() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        
+        // Query the config to check if it's set correctly
+        let config: Config = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.admin, Addr::unchecked(ADMIN));
+        assert_eq!(config.token_denom, TOKEN_DENOM);
+        assert_eq!(config.platform_fee, Uint128::new(100));
+    }
+
+    #[test]
+    fn test_migrate_version_checks() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // Migrating to the same version only applies the parameters
+        let msg = MigrateMsg { config: Some(ConfigUpdate { min_bet: Some(Uint128::new(2000)), ..ConfigUpdate::default() }) };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(attr(&res, "min_bet"), "2000");
+        let config: Config = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.min_bet, Uint128::new(2000));
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "9.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { config: None }).unwrap_err();
+        assert_eq!(err, ContractError::CannotDowngrade { from: "9.0.0".to_string(), to: CONTRACT_VERSION.to_string() });
+
+        set_contract_version(deps.as_mut().storage, "crates.io:other-contract", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { config: None }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidContractName { contract: "crates.io:other-contract".to_string() });
+    }
+
+    #[test]
+    fn test_migrate_from_v0_1() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let now = env.block.time.seconds();
+
+        // State as written by v0.1.0, which never stored a cw2 version
+        LEGACY_CONFIG.save(deps.as_mut().storage, &LegacyConfig {
+            admin: Addr::unchecked(ADMIN),
+            token_denom: TOKEN_DENOM.to_string(),
+            platform_fee: Uint128::new(100),
+            treasury: Addr::unchecked("treasury"),
+            challenging_period: 86400,
+            voting_period: 86400,
+            min_bet: Uint128::new(1000),
+            whitelist_enabled: false,
+        }).unwrap();
+        LEGACY_MARKETS.save(deps.as_mut().storage, 1, &LegacyMarket {
+            id: 1,
+            creator: Addr::unchecked(ADMIN),
+            question: "Who will win the World Cup Final?".to_string(),
+            description: "World Cup Final match details".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            category: "Sports".to_string(),
+            start_time: now - 100,
+            end_time: now + 10000,
+            status: MarketStatus::Active,
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            result: None,
+        }).unwrap();
+        let legacy_order = |id, creator: &str, side, filled_amount, status| LegacyOrder {
+            id,
+            market_id: 1,
+            creator: Addr::unchecked(creator),
+            option_id: 0,
+            side,
+            amount: Uint128::new(2000),
+            odds: 300,
+            filled_amount: Uint128::new(filled_amount),
+            status,
+            timestamp: now - 50,
+        };
+        LEGACY_ORDERS.save(deps.as_mut().storage, 1, &legacy_order(1, USER1, OrderSide::Back, 1000, OrderStatus::PartiallyFilled)).unwrap();
+        let mut lay = legacy_order(2, USER2, OrderSide::Lay, 1000, OrderStatus::Filled);
+        lay.amount = Uint128::new(1000);
+        LEGACY_ORDERS.save(deps.as_mut().storage, 2, &lay).unwrap();
+        MATCHED_BETS.save(deps.as_mut().storage, 1, &MatchedBet {
+            id: 1,
+            market_id: 1,
+            option_id: 0,
+            amount: Uint128::new(1000),
+            odds: 300,
+            timestamp: now - 50,
+            back_user: Addr::unchecked(USER1),
+            lay_user: Addr::unchecked(USER2),
+            redeemed: false,
+        }).unwrap();
+        // A market resolved under v0.1.0 with a bet still to redeem
+        LEGACY_MARKETS.save(deps.as_mut().storage, 2, &LegacyMarket {
+            id: 2,
+            creator: Addr::unchecked(ADMIN),
+            question: "Who will win the Euro Final?".to_string(),
+            description: "Euro Final match details".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            category: "Sports".to_string(),
+            start_time: now - 20000,
+            end_time: now - 10000,
+            status: MarketStatus::Resolved,
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            result: Some(0),
+        }).unwrap();
+        MATCHED_BETS.save(deps.as_mut().storage, 2, &MatchedBet {
+            id: 2,
+            market_id: 2,
+            option_id: 0,
+            amount: Uint128::new(1000),
+            odds: 300,
+            timestamp: now - 15000,
+            back_user: Addr::unchecked(USER3),
+            lay_user: Addr::unchecked(USER2),
+            redeemed: false,
+        }).unwrap();
+        MARKET_COUNT.save(deps.as_mut().storage, &2).unwrap();
+        ORDER_COUNT.save(deps.as_mut().storage, &2).unwrap();
+        MATCHED_BET_COUNT.save(deps.as_mut().storage, &2).unwrap();
+
+        let msg = MigrateMsg { config: Some(ConfigUpdate { guardian: Some(Addr::unchecked(GUARDIAN)), ..ConfigUpdate::default() }) };
+        let res = migrate(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(attr(&res, "from_version"), "0.1.0");
+        assert_eq!(attr(&res, "migrated_orders"), "2");
+        assert_eq!(cw2::get_contract_version(deps.as_ref().storage).unwrap().version, CONTRACT_VERSION);
+
+        let config: Config = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.guardian, Some(Addr::unchecked(GUARDIAN)));
+        assert_eq!(config.paused, PauseFlags::default());
+        let market: Market = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Market { market_id: 1 }).unwrap()).unwrap();
+        assert_eq!(market.option_statuses, vec![OptionStatus::Active, OptionStatus::Active]);
+
+        // Indexes, order book and positions are backfilled
+        let book: OrderBook = from_json(query(deps.as_ref(), env.clone(), QueryMsg::OrderBook { market_id: 1, option_id: 0, depth: None }).unwrap()).unwrap();
+        assert_eq!(book.back, vec![PriceLevel { odds: 300, amount: Uint128::new(1000), order_count: 1 }]);
+        assert!(book.lay.is_empty());
+        assert_eq!(book.last_matched_odds, Some(300));
+        let orders: Vec<Order> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::UserOrders {
+            user: Addr::unchecked(USER2), market_id: None, status: None, start_after: None, limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].time_in_force, TimeInForce::GoodTilCancelled);
+        let bets: Vec<MatchedBet> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MatchedBets {
+            market_id: Some(1), user: Some(Addr::unchecked(USER1)), start_after: None, limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(bets.len(), 1);
+        let position = POSITIONS.load(deps.as_ref().storage, (Addr::unchecked(USER2), 1, 0)).unwrap();
+        assert_eq!(position.lay_liability, Uint128::new(2000));
+
+        // Migrated orders behave like new ones
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CancelOrder { order_id: 1 }).unwrap();
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1000));
+
+        // The fee of the bet redeemed after the migration is pending for the market resolved before it
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id: 2 }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::new(20));
+        execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::RedeemMarket { market_id: 2 }).unwrap();
+        assert_eq!(balance(deps.as_ref(), USER3), Uint128::new(2980));
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id: 2 }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::new(20));
+
+        // Staking works on a migrated contract
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(5000) }]), ExecuteMsg::Stake {}).unwrap();
+        assert_eq!(attr(&res, "staked"), "5000");
+        assert_eq!(TOTAL_STAKED.load(deps.as_ref().storage).unwrap(), Uint128::new(5000));
+    }
+
+    #[test]
+    fn test_update_config() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        // Test updating config with valid parameters
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            platform_fee: Some(Uint128::new(200)),
+            ..ConfigUpdate::default()
+        });
+        let info = mock_info(ADMIN, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(2, res.attributes.len());
+
+        // Query the config to check if it's updated
+        let config: Config = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.platform_fee, Uint128::new(200));
+
+        // Test updating config with unauthorized user
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            platform_fee: Some(Uint128::new(300)),
+            ..ConfigUpdate::default()
+        });
+        let info = mock_info(USER1, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_update_config_multiple_fields() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let info = mock_info(ADMIN, &[]);
+
+        // Several fields change in one message, with one attribute each
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            token_denom: Some("ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2".to_string()),
+            voting_period: Some(172800),
+            min_bet: Some(Uint128::new(50)),
+            ..ConfigUpdate::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(4, res.attributes.len());
+        assert_eq!(attr(&res, "voting_period"), "172800");
+        assert_eq!(attr(&res, "min_bet"), "50");
+
+        let config: Config = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert!(config.token_denom.starts_with("ibc/"));
+        assert_eq!(config.voting_period, 172800);
+        assert_eq!(config.min_bet, Uint128::new(50));
+
+        // One invalid field rejects the whole update
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            voting_period: Some(86400),
+            token_denom: Some("1$".to_string()),
+            ..ConfigUpdate::default()
+        });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidDenom {});
+        let config: Config = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.voting_period, 172800);
+
+        // The guardian can be set and cleared, but not both at once
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { guardian: Some(Addr::unchecked(USER3)), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            guardian: Some(Addr::unchecked(USER3)),
+            clear_guardian: Some(true),
+            ..ConfigUpdate::default()
+        });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::ConflictingGuardianUpdate {});
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { clear_guardian: Some(true), ..ConfigUpdate::default() });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(attr(&res, "guardian"), "none");
+        let config: Config = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.guardian, None);
+
+        // Fields set to their current value are not reported as changed
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            voting_period: Some(172800),
+            min_bet: Some(Uint128::new(1000)),
+            ..ConfigUpdate::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(2, res.attributes.len());
+        assert_eq!(attr(&res, "min_bet"), "1000");
+
+        // The denom can change while the contract owes nothing, but is locked while funds are outstanding
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { token_denom: Some(TOKEN_DENOM.to_string()), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let market_id = create_open_market(deps.as_mut(), &mock_env());
+        place_limit_order(deps.as_mut(), &mock_env(), USER1, market_id, OrderSide::Back, 1000, 200);
+        let denom_update = ExecuteMsg::UpdateConfig(ConfigUpdate { token_denom: Some("uother".to_string()), ..ConfigUpdate::default() });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), denom_update.clone()).unwrap_err();
+        assert_eq!(err, ContractError::DenomInUse {});
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::CancelMarket { market_id }).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), denom_update.clone()).unwrap_err();
+        assert_eq!(err, ContractError::DenomInUse {});
+
+        // Once the refunded stake is withdrawn nothing is owed
+        execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), ExecuteMsg::Withdraw { amount: Uint128::new(1000) }).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), denom_update).unwrap();
+        assert_eq!(attr(&res, "token_denom"), "uother");
+
+        // An update that changes nothing is rejected
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { voting_period: Some(172800), ..ConfigUpdate::default() });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::EmptyConfigUpdate {});
+
+        // An update that sets nothing is rejected
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate::default());
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::EmptyConfigUpdate {});
+    }
+
+    #[test]
+    fn test_two_step_admin_transfer() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let mut env = mock_env();
+
+        // Only the admin can propose, and the expiry must be in the future
+        let propose = |expires_at| ExecuteMsg::ProposeNewAdmin { new_admin: Addr::unchecked(USER1), expires_at };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), propose(None)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), propose(Some(env.block.time.seconds()))).unwrap_err();
+        assert_eq!(err, ContractError::InvalidExpiry {});
+
+        let expires_at = env.block.time.seconds() + 100;
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), propose(Some(expires_at))).unwrap();
+        let pending: Option<PendingAdmin> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::PendingAdmin {}).unwrap()).unwrap();
+        assert_eq!(pending, Some(PendingAdmin { new_admin: Addr::unchecked(USER1), expires_at: Some(expires_at) }));
+
+        // The admin does not change until the transfer is accepted
+        let config: Config = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.admin, Addr::unchecked(ADMIN));
+
+        // Only the proposed address can accept
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // Cancelling removes the pending transfer
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CancelAdminTransfer {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::CancelAdminTransfer {}).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert_eq!(err, ContractError::NoPendingAdmin {});
+
+        // An expired transfer cannot be accepted
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), propose(Some(expires_at))).unwrap();
+        env.block.time = env.block.time.plus_seconds(100);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert_eq!(err, ContractError::AdminTransferExpired {});
+
+        // A transfer without expiry hands over admin rights once accepted
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), propose(None)).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::AcceptAdmin {}).unwrap();
+        assert_eq!(attr(&res, "previous_admin"), ADMIN);
+        let config: Config = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.admin, Addr::unchecked(USER1));
+        let pending: Option<PendingAdmin> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::PendingAdmin {}).unwrap()).unwrap();
+        assert_eq!(pending, None);
+
+        // The previous admin has lost its rights
+        let err = execute(deps.as_mut(), env, mock_info(ADMIN, &[]), ExecuteMsg::CancelAdminTransfer {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_roles() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+
+        // Only the admin can grant roles
+        let grant = |role, address: &str| ExecuteMsg::GrantRole { role, address: Addr::unchecked(address) };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), grant(Role::Operator, USER1)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), grant(Role::Operator, USER1)).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), grant(Role::Resolver, USER1)).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), grant(Role::WhitelistManager, USER2)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), grant(Role::Operator, USER1)).unwrap_err();
+        assert_eq!(err, ContractError::RoleAlreadyGranted {});
+
+        let roles: Vec<Role> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Roles { address: Addr::unchecked(USER1) }).unwrap()).unwrap();
+        assert_eq!(roles, vec![Role::Operator, Role::Resolver]);
+        let members: Vec<String> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::RoleMembers { role: Role::WhitelistManager, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(members, vec![USER2.to_string()]);
+        let has_role: bool = from_json(query(deps.as_ref(), env.clone(), QueryMsg::HasRole { role: Role::FeeManager, address: Addr::unchecked(ADMIN) }).unwrap()).unwrap();
+        assert!(has_role);
+
+        // Each role only unlocks its own actions
+        let whitelist = ExecuteMsg::AddToWhitelist { address: Addr::unchecked(USER3) };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), whitelist.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), whitelist).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::SuspendMarket { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::SuspendMarket { market_id }).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::ResumeMarket { market_id }).unwrap();
+        let other_market_id = create_open_market(deps.as_mut(), &env);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::CancelMarket { market_id: other_market_id }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CancelMarket { market_id: other_market_id }).unwrap();
+
+        // An operator closes the market and a resolver finalizes the proposal
+        env.block.time = env.block.time.plus_seconds(10001);
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CloseMarket { market_id }).unwrap();
+        let propose_msg = ExecuteMsg::ProposeResult { market_id, winning_outcome: 0 };
+        execute(deps.as_mut(), env.clone(), mock_info(USER3, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]), propose_msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(86401);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+
+        // Revoked roles no longer grant access
+        let revoke = ExecuteMsg::RevokeRole { role: Role::WhitelistManager, address: Addr::unchecked(USER2) };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), revoke.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), revoke.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), revoke).unwrap_err();
+        assert_eq!(err, ContractError::RoleNotGranted {});
+        let whitelist = ExecuteMsg::RemoveFromWhitelist { address: Addr::unchecked(USER3) };
+        let err = execute(deps.as_mut(), env, mock_info(USER2, &[]), whitelist).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_emergency_pause() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 2000, 300);
+
+        // Only the admin or the guardian can pause
+        let pause = |scope| ExecuteMsg::Pause { scope };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), pause(None)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(GUARDIAN, &[]), pause(Some(PauseScope::Trading))).unwrap();
+        assert_eq!(attr(&res, "scope"), "trading");
+
+        // Trading is halted but cancellations and withdrawals still go through
+        let order = ExecuteMsg::PlaceOrder {
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Lay,
+            amount: Uint128::new(2000),
+            odds: 300,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(4000) }]), order.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Paused { scope: "trading".to_string() });
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CancelOrder { order_id: 1 }).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::Withdraw { amount: Uint128::new(2000) }).unwrap();
+
+        // Pausing without a scope halts everything, including market creation
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), pause(None)).unwrap();
+        let config: Config = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.paused, PauseFlags { trading: true, market_creation: true, redemptions: true, disputes: true });
+        let create_msg = ExecuteMsg::CreateMarket {
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(),
+            description: "World Cup Final match details".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: (env.block.time.seconds() + 5).to_string(),
+            end_time: (env.block.time.seconds() + 10000).to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(500000) }]), create_msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused { scope: "market_creation".to_string() });
+
+        // Unpausing one scope leaves the others paused
+        execute(deps.as_mut(), env.clone(), mock_info(GUARDIAN, &[]), ExecuteMsg::Unpause { scope: Some(PauseScope::Trading) }).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(4000) }]), order).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::RedeemMarket { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::Paused { scope: "redemptions".to_string() });
+
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::Unpause { scope: None }).unwrap();
+        let config: Config = from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.paused, PauseFlags::default());
+    }
 
-    Ok((votes, vote_counts))
-}
+    #[test]
+    fn test_losing_bond_is_split() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+
+        // The challenger wins the vote, so the proposer's bond is slashed
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+        vote(&mut deps, &env, USER1, market_id, 1);
+        vote(&mut deps, &env, "voter2", market_id, 1);
+        vote(&mut deps, &env, "voter3", market_id, 0);
+
+        env.block.time = env.block.time.plus_seconds(86400 * 2 + 1);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "winner"), USER2);
+        assert_eq!(attr(&res, "winner_bond_share"), "500000");
+        assert_eq!(attr(&res, "voter_reward_pool"), "300000");
+        assert_eq!(attr(&res, "treasury_bond_share"), "200000");
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: vec![Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(200000) }],
+        }));
 
-fn query_whitelisted_addresses(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<String>> {
-    let start = start_after.map(|s| Addr::unchecked(s));
-    let limit = limit.unwrap_or(30) as usize;
+        // Only voters of the winning outcome are rewarded
+        let reward = |voter: &str| -> VoterRewardResponse {
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::VoterReward { market_id, voter: Addr::unchecked(voter) }).unwrap()).unwrap()
+        };
+        assert_eq!(reward(USER1).claimable, Uint128::new(150000));
+        assert_eq!(reward("voter2").claimable, Uint128::new(150000));
+        assert_eq!(reward("voter3").claimable, Uint128::zero());
+
+        // The winner redeems their bond with their share of the loser's bond, once
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::RedeemBondAmount { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::NotWinner {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::RedeemBondAmount { market_id }).unwrap();
+        assert_eq!(attr(&res, "amount"), "1500000");
+        let err = execute(deps.as_mut(), env, mock_info(USER2, &[]), ExecuteMsg::RedeemBondAmount { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::BondAlreadyRedeemed {});
+
+        // Shares must add up to the whole bond
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            bond_split: Some(BondSplit { winner: 5000, voters: 5000, treasury: 1 }),
+            ..ConfigUpdate::default()
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidBondSplit {});
+    }
 
-    let addresses: Vec<String> = WHITELISTED_ADDRESSES
-        .range(deps.storage, start.map(Bound::exclusive), None, cosmwasm_std::Order::Ascending)
-        .take(limit)
-        .filter_map(|item| item.ok().map(|(addr, _)| addr.to_string()))
-        .collect();
+    #[test]
+    fn test_void_dispute_outcome() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, "layer", market_id, OrderSide::Lay, 1000, 200);
+
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+
+        // Votes must be an option of the market or the void outcome
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::AddToWhitelist { address: Addr::unchecked("voter1") }).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), ExecuteMsg::CastVote { market_id, outcome: 2 }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVote {});
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), ExecuteMsg::CastVote { market_id, outcome: VOID_OUTCOME }).unwrap();
+        vote(&mut deps, &env, "voter2", market_id, VOID_OUTCOME);
+        vote(&mut deps, &env, "voter3", market_id, 1);
+
+        env.block.time = env.block.time.plus_seconds(86400 * 2 + 1);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "winning_outcome"), VOID_OUTCOME.to_string());
+
+        // A void result refunds the market, the resolution reward goes back to its creator
+        assert_eq!(attr(&res, "winner"), ADMIN);
+        assert!(res.messages.iter().any(|msg| msg.msg == CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADMIN.to_string(),
+            amount: vec![Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(500000) }],
+        })));
+
+        // Both sides of the matched bet get their stake back and nothing is left to redeem
+        let market: Market = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Market { market_id }).unwrap()).unwrap();
+        assert_eq!(market.result, Some(VOID_OUTCOME));
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1000));
+        assert_eq!(balance(deps.as_ref(), "layer"), Uint128::new(1000));
+        let err = execute(deps.as_mut(), env, mock_info(USER1, &[]), ExecuteMsg::RedeemMarket { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::NothingToRedeem {});
+    }
 
-    Ok(addresses)
-}
+    #[test]
+    fn test_dispute_tie_and_quorum_rules() {
+        let resolve = |deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>, env: &mut Env, market_id| {
+            env.block.time = env.block.time.plus_seconds(86400 * 2 + 1);
+            execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap()
+        };
+        let set_rules = |deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>, rules: DisputeRules| {
+            let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { dispute_rules: Some(rules), ..ConfigUpdate::default() });
+            execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{Addr, Uint128, Coin, DepsMut, from_json};
-    use cosmwasm_std::Timestamp;
+        // A tie leaves the original outcome standing by default
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+        vote(&mut deps, &env, "voter1", market_id, 1);
+        vote(&mut deps, &env, "voter2", market_id, 0);
+        let res = resolve(&mut deps, &mut env, market_id);
+        assert_eq!(attr(&res, "rule"), "tie");
+        assert_eq!(attr(&res, "fallback"), "original_stands");
+        assert_eq!(attr(&res, "winning_outcome"), "0");
+        assert_eq!(attr(&res, "winner"), USER3);
+
+        // Missing the quorum can void the market
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        set_rules(&mut deps, DisputeRules { quorum: 3, no_quorum_rule: FallbackRule::Void, ..DisputeRules::default() });
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+        vote(&mut deps, &env, "voter1", market_id, 1);
+        vote(&mut deps, &env, "voter2", market_id, 1);
+        let res = resolve(&mut deps, &mut env, market_id);
+        assert_eq!(attr(&res, "rule"), "no_quorum");
+        assert_eq!(attr(&res, "fallback"), "void");
+        assert_eq!(attr(&res, "winning_outcome"), VOID_OUTCOME.to_string());
+
+        // A leader short of the supermajority is escalated to the resolvers
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        set_rules(&mut deps, DisputeRules { supermajority: 6700, tie_rule: FallbackRule::Escalate, ..DisputeRules::default() });
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+        vote(&mut deps, &env, "voter1", market_id, 1);
+        vote(&mut deps, &env, "voter2", market_id, 1);
+        vote(&mut deps, &env, "voter3", market_id, 0);
+        let res = resolve(&mut deps, &mut env, market_id);
+        assert_eq!(attr(&res, "rule"), "no_supermajority");
+        assert_eq!(attr(&res, "result"), "dispute_escalated");
+        let dispute: Dispute = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Dispute { market_id }).unwrap()).unwrap();
+        assert_eq!(dispute.status, DisputeStatus::Escalated);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::DisputeEscalated {});
+
+        let decide = |outcome| ExecuteMsg::ResolveEscalatedDispute { market_id, outcome };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), decide(1)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), decide(1)).unwrap();
+        assert_eq!(attr(&res, "winning_outcome"), "1");
+        assert_eq!(attr(&res, "winner"), USER2);
+        assert_eq!(attr(&res, "voter_reward_pool"), "300000");
+        let dispute: Dispute = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Dispute { market_id }).unwrap()).unwrap();
+        assert_eq!(dispute.status, DisputeStatus::Resolved);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), decide(1)).unwrap_err();
+        assert_eq!(err, ContractError::DisputeNotEscalated {});
+
+        // Two thirds of the votes reach the supermajority
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        set_rules(&mut deps, DisputeRules { supermajority: 6600, tie_rule: FallbackRule::Escalate, ..DisputeRules::default() });
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+        vote(&mut deps, &env, "voter1", market_id, 1);
+        vote(&mut deps, &env, "voter2", market_id, 1);
+        vote(&mut deps, &env, "voter3", market_id, 0);
+        let res = resolve(&mut deps, &mut env, market_id);
+        assert_eq!(attr(&res, "rule"), "majority");
+        assert_eq!(attr(&res, "winning_outcome"), "1");
+        let dispute: Dispute = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Dispute { market_id }).unwrap()).unwrap();
+        assert_eq!(dispute.status, DisputeStatus::Resolved);
+    }
 
-    const ADMIN: &str = "admin";
-    const USER1: &str = "user1";
-    const USER2: &str = "user2";
-    const USER3: &str = "user3";
-    const TOKEN_DENOM: &str = "utoken";
+    #[test]
+    fn test_commit_reveal_voting() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { vote_mode: Some(VoteMode::CommitReveal { reveal_period: 172800 }), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
 
-    fn setup_contract(deps: DepsMut) {
-        let msg = InstantiateMsg {
-            admin: Addr::unchecked(ADMIN),
-            token_denom: TOKEN_DENOM.to_string(),
-            platform_fee: Uint128::new(100),  // 1%
-            treasury: Addr::unchecked("treasury"),
-            challenging_period: 86400,  // 1 day
-            voting_period: 86400,  // 1 day
-            min_bet: Uint128::new(1000),
-            whitelist_enabled: false,
+        for voter in ["voter1", "voter2", "voter3"] {
+            execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::AddToWhitelist { address: Addr::unchecked(voter) }).unwrap();
+        }
+        let commit = |voter: &str, outcome, salt| ExecuteMsg::CommitVote {
+            market_id,
+            hash: vote_commitment(market_id, &Addr::unchecked(voter), outcome, salt),
         };
-        let info = mock_info(ADMIN, &[]);
-        let _ = instantiate(deps, mock_env(), info, msg).unwrap();
+
+        // Votes cannot be cast in the clear
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), ExecuteMsg::CastVote { market_id, outcome: 1 }).unwrap_err();
+        assert_eq!(err, ContractError::CommitRevealRequired {});
+        let malformed = ExecuteMsg::CommitVote { market_id, hash: "not-a-sha256-digest".to_string() };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), malformed).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVoteHash {});
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), commit("voter1", 1, "salt1")).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("voter2", &[]), commit("voter2", 1, "salt2")).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("voter3", &[]), commit("voter3", 0, "salt3")).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), commit("voter1", 0, "salt1")).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+
+        // The windows were fixed when the dispute was raised, a later config change does not move them
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { voting_period: Some(172800), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        // Reveals only open once the voting period is over
+        let reveal = |outcome, salt: &str| ExecuteMsg::RevealVote { market_id, outcome, salt: salt.to_string() };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), reveal(1, "salt1")).unwrap_err();
+        assert_eq!(err, ContractError::RevealPeriodNotStarted {});
+        env.block.time = env.block.time.plus_seconds(86401);
+
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), reveal(0, "salt1")).unwrap_err();
+        assert_eq!(err, ContractError::InvalidReveal {});
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), reveal(1, "salt1")).unwrap_err();
+        assert_eq!(err, ContractError::NoCommittedVote {});
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), reveal(1, "salt1")).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("voter3", &[]), reveal(0, "salt3")).unwrap();
+
+        // Tallies stay hidden and the dispute cannot be resolved while reveals are open
+        assert!(query(deps.as_ref(), env.clone(), QueryMsg::Votes { market_id }).is_err());
+        env.block.time = env.block.time.plus_seconds(172799);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::VotingPeriodNotEnded {});
+
+        // voter2 never revealed, so its vote is ignored and the tie lets the proposal stand
+        env.block.time = env.block.time.plus_seconds(1);
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter2", &[]), reveal(1, "salt2")).unwrap_err();
+        assert_eq!(err, ContractError::RevealPeriodEnded {});
+        let (_, counts): (Vec<Vote>, Vec<(u8, u64)>) = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Votes { market_id }).unwrap()).unwrap();
+        assert_eq!(counts, vec![(0, 1), (1, 1)]);
+        let res = execute(deps.as_mut(), env, mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "rule"), "tie");
+        assert_eq!(attr(&res, "winning_outcome"), "0");
+        assert_eq!(balance(deps.as_ref(), "voter2"), Uint128::zero());
     }
 
     #[test]
-    fn test_instantiation() {
+    fn test_stake_weighted_voting() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
-        
-        // Query the config to check if it's set correctly
-        let config: Config = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
-        assert_eq!(config.admin, Addr::unchecked(ADMIN));
-        assert_eq!(config.token_denom, TOKEN_DENOM);
-        assert_eq!(config.platform_fee, Uint128::new(100));
+        let staking = StakingConfig { unbonding_period: 172800, stake_weighted_voting: true, slash_rate: 5000 };
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { staking: Some(staking), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let stake = |amount| [Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(amount) }];
+
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &stake(300)), ExecuteMsg::Stake {}).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("voter2", &stake(100)), ExecuteMsg::Stake {}).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("voter3", &stake(150)), ExecuteMsg::Stake {}).unwrap();
+        env.block.height += 1;
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+
+        // Stake added after the dispute was raised does not count
+        env.block.height += 1;
+        execute(deps.as_mut(), env.clone(), mock_info("voter3", &stake(500)), ExecuteMsg::Stake {}).unwrap();
+        let power: u64 = from_json(query(deps.as_ref(), env.clone(), QueryMsg::VotingPower { market_id, address: Addr::unchecked("voter3") }).unwrap()).unwrap();
+        assert_eq!(power, 150);
+
+        // Whitelisting does not give voting power without stake
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::AddToWhitelist { address: Addr::unchecked(USER1) }).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CastVote { market_id, outcome: 0 }).unwrap_err();
+        assert_eq!(err, ContractError::NoVotingPower {});
+
+        // Two voters with less stake lose to a single larger one
+        execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), ExecuteMsg::CastVote { market_id, outcome: 0 }).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("voter2", &[]), ExecuteMsg::CastVote { market_id, outcome: 1 }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info("voter3", &[]), ExecuteMsg::CastVote { market_id, outcome: 1 }).unwrap();
+        assert_eq!(attr(&res, "weight"), "150");
+        execute(deps.as_mut(), env.clone(), mock_info("voter2", &[]), ExecuteMsg::Unstake { amount: Uint128::new(100) }).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter2", &[]), ExecuteMsg::ClaimUnstaked {}).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        env.block.time = env.block.time.plus_seconds(172801);
+        let (_, counts): (Vec<Vote>, Vec<(u8, u64)>) = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Votes { market_id }).unwrap()).unwrap();
+        assert_eq!(counts, vec![(0, 300), (1, 250)]);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "winning_outcome"), "0");
+
+        // Losing voters are slashed half their voting stake, unbonding tokens included
+        assert_eq!(attr(&res, "slashed_stake"), "125");
+        let voter2: StakeResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Stake { address: Addr::unchecked("voter2") }).unwrap()).unwrap();
+        assert_eq!(voter2.staked, Uint128::zero());
+        assert_eq!(voter2.unbonding[0].amount, Uint128::new(50));
+        let voter3: StakeResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Stake { address: Addr::unchecked("voter3") }).unwrap()).unwrap();
+        assert_eq!(voter3.staked, Uint128::new(575));
+        assert_eq!(voter3.total_staked, Uint128::new(875));
+
+        // The unbonding period is over, voter2 claims what is left after the slash
+        let res = execute(deps.as_mut(), env, mock_info("voter2", &[]), ExecuteMsg::ClaimUnstaked {}).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: "voter2".to_string(), amount: stake(50).to_vec() }));
     }
 
     #[test]
-    fn test_update_config() {
+    fn test_dispute_appeal_rounds() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
+        let escalation = EscalationConfig { max_appeals: 2, bond_multiplier: 2, appeal_period: 86400 };
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { escalation: Some(escalation), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let bond = |amount| [Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(amount) }];
+        let appeal = |proposed_outcome| ExecuteMsg::AppealDispute { market_id, proposed_outcome, evidence: "Updated match report".to_string() };
+
+        // Round 1 overturns the proposal
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+        vote(&mut deps, &env, "voter1", market_id, 1);
+        env.block.time = env.block.time.plus_seconds(172801);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "result"), "round_decided");
+        assert_eq!(attr(&res, "round_outcome"), "1");
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::AppealPeriodNotEnded {});
+
+        // Appeals must challenge the result with twice the previous bond
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER3, &bond(2000000)), appeal(1)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidAppeal {});
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER3, &bond(1000000)), appeal(0)).unwrap_err();
+        assert_eq!(err, ContractError::IncorrectBondAmount {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &bond(2000000)), appeal(0)).unwrap();
+        assert_eq!(attr(&res, "round"), "2");
+        assert_eq!(attr(&res, "final_round"), "false");
+
+        // Round 2 is voted afresh and reverses round 1
+        vote(&mut deps, &env, "voter2", market_id, 0);
+        vote(&mut deps, &env, "voter3", market_id, 0);
+        env.block.time = env.block.time.plus_seconds(86401);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "round_outcome"), "0");
+
+        // The last appeal leaves the final round to the resolvers
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &bond(4000000)), appeal(1)).unwrap();
+        assert_eq!(attr(&res, "final_round"), "true");
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), ExecuteMsg::CastVote { market_id, outcome: 1 }).unwrap_err();
+        assert_eq!(err, ContractError::DisputeEscalated {});
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::DisputeEscalated {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveEscalatedDispute { market_id, outcome: 1 }).unwrap();
+        assert_eq!(attr(&res, "winning_outcome"), "1");
+
+        // The proposer's and USER3's 3000000 bonds are lost: half goes to USER2's two rounds pro rata,
+        // 30% to voter1's vote for option 1 and the rest to the treasury
+        assert_eq!(attr(&res, "winner_bond_share"), "1500000");
+        assert_eq!(attr(&res, "voter_reward_pool"), "900000");
+        assert_eq!(attr(&res, "treasury_bond_share"), "600000");
+
+        let rounds: Vec<DisputeRound> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::DisputeRounds { market_id }).unwrap()).unwrap();
+        assert_eq!(rounds.iter().map(|round| round.result).collect::<Vec<_>>(), vec![Some(1), Some(0), Some(1)]);
+        assert_eq!(rounds.iter().map(|round| round.bond.u128()).collect::<Vec<_>>(), vec![1000000, 2000000, 4000000]);
+        assert_eq!(rounds[1].vote_counts, vec![(0, 2)]);
+        assert_eq!(rounds[0].winner_bond_share, Uint128::new(300000));
+        let dispute: Dispute = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Dispute { market_id }).unwrap()).unwrap();
+        assert_eq!((dispute.status, dispute.appeal_deadline), (DisputeStatus::Resolved, None));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::RedeemBondAmount { market_id }).unwrap();
+        assert_eq!(attr(&res, "amount"), "6500000");
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::RedeemBondAmount { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::BondAlreadyRedeemed {});
+        let err = execute(deps.as_mut(), env, mock_info(USER3, &[]), ExecuteMsg::RedeemBondAmount { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::NotWinner {});
+    }
 
-        // Test updating config with valid parameters
-        let msg = ExecuteMsg::UpdateConfig {
-            field: "platform_fee".to_string(),
-            value: "200".to_string(),
-        };
-        let info = mock_info(ADMIN, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(3, res.attributes.len());
+    #[test]
+    fn test_appeal_round_without_quorum() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let escalation = EscalationConfig { max_appeals: 2, bond_multiplier: 2, appeal_period: 86400 };
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { escalation: Some(escalation), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+
+        // Round 1 overturns the proposal
+        dispute_market(&mut deps, &mut env, market_id, 0, 1);
+        vote(&mut deps, &env, "voter1", market_id, 1);
+        vote(&mut deps, &env, "voter2", market_id, 1);
+        env.block.time = env.block.time.plus_seconds(172801);
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+
+        // Nobody votes on the appeal, so round 1's result stands rather than the proposal
+        let appeal = ExecuteMsg::AppealDispute { market_id, proposed_outcome: 0, evidence: "Updated match report".to_string() };
+        execute(deps.as_mut(), env.clone(), mock_info(USER3, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(2000000) }]), appeal).unwrap();
+        env.block.time = env.block.time.plus_seconds(86401);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "rule"), "no_quorum");
+        assert_eq!(attr(&res, "fallback"), "original_stands");
+        assert_eq!(attr(&res, "round_outcome"), "1");
 
-        // Query the config to check if it's updated
-        let config: Config = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
-        assert_eq!(config.platform_fee, Uint128::new(200));
+        env.block.time = env.block.time.plus_seconds(86401);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "winning_outcome"), "1");
+        assert_eq!(attr(&res, "winner"), USER2);
+        let market: Market = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Market { market_id }).unwrap()).unwrap();
+        assert_eq!(market.result, Some(1));
+        let dispute: Dispute = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Dispute { market_id }).unwrap()).unwrap();
+        assert_eq!((dispute.status, dispute.appeal_deadline), (DisputeStatus::Resolved, None));
+        let err = execute(deps.as_mut(), env, mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidProposalState {});
+    }
 
-        // Test updating config with unauthorized user
-        let msg = ExecuteMsg::UpdateConfig {
-            field: "platform_fee".to_string(),
-            value: "300".to_string(),
+    #[test]
+    fn test_voter_reward_pool() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { voter_fee_share: Some(5000), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        let mut env = mock_env();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, "bettor1", market_id, OrderSide::Back, 100000, 200);
+        place_limit_order(deps.as_mut(), &env, "bettor2", market_id, OrderSide::Lay, 100000, 200);
+
+        dispute_market(&mut deps, &mut env, market_id, 1, 0);
+        vote(&mut deps, &env, "voter1", market_id, 0);
+        vote(&mut deps, &env, "voter2", market_id, 0);
+        vote(&mut deps, &env, "voter3", market_id, 1);
+        env.block.time = env.block.time.plus_seconds(172801);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResolveDispute { market_id }).unwrap();
+        assert_eq!(attr(&res, "voter_reward_pool"), "300000");
+
+        // Half of the 1000 fee on the winnings goes to the pool as the market resolves
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::new(500));
+        let reward = |deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>| -> VoterRewardResponse {
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::VoterReward { market_id, voter: Addr::unchecked("voter1") }).unwrap()).unwrap()
         };
-        let info = mock_info(USER1, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, msg);
-        assert!(res.is_err());
+        assert_eq!(reward(&deps).claimable, Uint128::new(150250));
+        assert_eq!(reward(&deps).pool.unwrap().fee_rewards, Uint128::new(500));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("voter1", &[]), ExecuteMsg::ClaimVoterReward { market_id }).unwrap();
+        assert_eq!(attr(&res, "amount"), "150250");
+        assert_eq!(reward(&deps).claimable, Uint128::zero());
+        assert_eq!(reward(&deps).claimed, Uint128::new(150250));
+
+        // Redeeming the winnings accrues nothing more
+        execute(deps.as_mut(), env.clone(), mock_info("bettor1", &[]), ExecuteMsg::RedeemWinnings { matched_bet_id: 1 }).unwrap();
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::new(500));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info("voter2", &[]), ExecuteMsg::ClaimVoterReward { market_id }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "voter2".to_string(),
+            amount: vec![Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(150250) }],
+        }));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("voter2", &[]), ExecuteMsg::ClaimVoterReward { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::NoVoterReward {});
+        let err = execute(deps.as_mut(), env, mock_info("voter3", &[]), ExecuteMsg::ClaimVoterReward { market_id }).unwrap_err();
+        assert_eq!(err, ContractError::NoVoterReward {});
     }
 
     #[test]
@@ -1456,6 +4791,7 @@ mod tests {
             end_time: "2000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
@@ -1515,56 +4851,203 @@ mod tests {
         let msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final".to_string(),
+            description: "World Cup Final".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: "1000000".to_string(),
+            end_time: "2000000".to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let info = mock_info(ADMIN, &[Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: Uint128::new(500000),
+        }]);
+        
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(3, res.attributes.len());
+
+        // Query the market to check if it's created correctly
+        let res: Market = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Market { market_id: 1 }).unwrap()).unwrap();
+        assert_eq!(res.description, "World Cup Final");
+        assert_eq!(res.options, vec!["Team A".to_string(), "Team B".to_string()]);
+    }
+
+    #[test]
+    fn test_create_market_invalid_params() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::CreateMarket { 
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(), // New field
+            description: "Invalid Time Range".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: "2000000".to_string(),  // Start time after end time
+            end_time: "1000000".to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let info = mock_info(ADMIN, &[Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: Uint128::new(500000),
+        }]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_cancel_market() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        // First, create a market
+        let create_msg = ExecuteMsg::CreateMarket { 
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(), // New field
+            description: "World Cup Final match details".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: "1000000".to_string(),
+            end_time: "2000000".to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let info = mock_info(ADMIN, &[Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: Uint128::new(500000),
+        }]);
+        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
+
+        // Now, cancel the market
+        let cancel_msg = ExecuteMsg::CancelMarket { market_id: 1 };
+        let info = mock_info(ADMIN, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, cancel_msg).unwrap();
+        assert_eq!(2, res.attributes.len());
+
+        // Verify that the market is canceled
+        let res: Market = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Market { market_id: 1 }).unwrap()).unwrap();
+        assert_eq!(res.status, MarketStatus::Canceled);
+    }
+
+    #[test]
+    fn test_close_market() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        // First, create a market
+        let create_msg = ExecuteMsg::CreateMarket { 
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(), // New field
+            description: "World Cup Final match details".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: "1000000".to_string(),
+            end_time: "2000000".to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let info = mock_info(ADMIN, &[Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: Uint128::new(500000),
+        }]);
+        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
+
+        // Now, close the market
+        let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
+        let info = mock_info(ADMIN, &[]);
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(2000001);  // Set time after end_time
+        let res = execute(deps.as_mut(), env, info, close_msg).unwrap();
+        assert_eq!(2, res.attributes.len());
+
+        // Verify that the market is closed
+        let res: Market = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Market { market_id: 1 }).unwrap()).unwrap();
+        assert_eq!(res.status, MarketStatus::Closed);
+    }
+
+    #[test]
+    fn test_unauthorized_market_modification() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        // First, create a market
+        let create_msg = ExecuteMsg::CreateMarket { 
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(), // New field
+            description: "World Cup Final match details".to_string(),
             options: vec!["Team A".to_string(), "Team B".to_string()],
             start_time: "1000000".to_string(),
             end_time: "2000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
             amount: Uint128::new(500000),
         }]);
-        
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(3, res.attributes.len());
+        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
 
-        // Query the market to check if it's created correctly
-        let res: Market = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Market { market_id: 1 }).unwrap()).unwrap();
-        assert_eq!(res.description, "World Cup Final");
-        assert_eq!(res.options, vec!["Team A".to_string(), "Team B".to_string()]);
+        // Attempt to cancel the market by non-admin
+        let cancel_msg = ExecuteMsg::CancelMarket { market_id: 1 };
+        let info = mock_info(USER1, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, cancel_msg);
+        assert!(res.is_err());
     }
 
     #[test]
-    fn test_create_market_invalid_params() {
+    fn test_place_valid_order() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
 
-        let msg = ExecuteMsg::CreateMarket { 
+        // First, create a market
+        let create_msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "Invalid Time Range".to_string(),
+            description: "World Cup Final".to_string(),
             options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: "2000000".to_string(),  // Start time after end time
-            end_time: "1000000".to_string(),
+            start_time: "100000000".to_string(),
+            end_time: "10000000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
             amount: Uint128::new(500000),
         }]);
-        let res = execute(deps.as_mut(), mock_env(), info, msg);
-        assert!(res.is_err());
+        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
+
+        // Now, place an order
+        let place_order_msg = ExecuteMsg::PlaceOrder { 
+            market_id: 1,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Back,
+            amount: Uint128::new(1000),
+            odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);
+        let res = execute(deps.as_mut(), mock_env(), info, place_order_msg).unwrap();
+        assert!(res.attributes.len() > 0);
+
+        // Verify that the order was placed
+        let res: Vec<Order> = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::UserOrders { user: Addr::unchecked(USER1), market_id: Some(1), status: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].amount, Uint128::new(1000));
+        assert_eq!(res[0].odds, 150);
     }
 
     #[test]
-    fn test_cancel_market() {
+    fn test_place_order_insufficient_funds() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
 
-        // First, create a market
+        // Create a market
         let create_msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
@@ -1574,6 +5057,7 @@ mod tests {
             end_time: "2000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
@@ -1581,23 +5065,28 @@ mod tests {
         }]);
         let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
 
-        // Now, cancel the market
-        let cancel_msg = ExecuteMsg::CancelMarket { market_id: 1 };
-        let info = mock_info(ADMIN, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, cancel_msg).unwrap();
-        assert_eq!(2, res.attributes.len());
-
-        // Verify that the market is canceled
-        let res: Market = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Market { market_id: 1 }).unwrap()).unwrap();
-        assert_eq!(res.status, MarketStatus::Canceled);
+        // Attempt to place an order with insufficient funds
+        let place_order_msg = ExecuteMsg::PlaceOrder { 
+            market_id: 1,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Back,
+            amount: Uint128::new(2000),  // Amount greater than min_bet
+            odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);  // Insufficient funds
+        let res = execute(deps.as_mut(), mock_env(), info, place_order_msg);
+        assert!(res.is_err());
     }
 
     #[test]
-    fn test_close_market() {
+    fn test_place_order_closed_market() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
 
-        // First, create a market
+        // Create and close a market
         let create_msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
@@ -1607,41 +5096,51 @@ mod tests {
             end_time: "2000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
             amount: Uint128::new(500000),
         }]);
-        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
+        let _ = execute(deps.as_mut(), mock_env(), info.clone(), create_msg).unwrap();
 
-        // Now, close the market
         let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
-        let info = mock_info(ADMIN, &[]);
         let mut env = mock_env();
-        env.block.time = env.block.time.plus_seconds(2000001);  // Set time after end_time
-        let res = execute(deps.as_mut(), env, info, close_msg).unwrap();
-        assert_eq!(2, res.attributes.len());
+        env.block.time = Timestamp::from_seconds(2000001);  // Set time after end_time
+        let _ = execute(deps.as_mut(), env.clone(), info, close_msg).unwrap();
 
-        // Verify that the market is closed
-        let res: Market = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Market { market_id: 1 }).unwrap()).unwrap();
-        assert_eq!(res.status, MarketStatus::Closed);
+        // Attempt to place an order on the closed market
+        let place_order_msg = ExecuteMsg::PlaceOrder { 
+            market_id: 1,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Back,
+            amount: Uint128::new(1000),
+            odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);
+        let res = execute(deps.as_mut(), env, info, place_order_msg);
+        assert!(res.is_err());
     }
 
     #[test]
-    fn test_unauthorized_market_modification() {
+    fn test_order_matching() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
 
-        // First, create a market
+        // Create a market
         let create_msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final match details".to_string(),
+            description: "World Cup Final".to_string(),
             options: vec!["Team A".to_string(), "Team B".to_string()],
             start_time: "1000000".to_string(),
-            end_time: "2000000".to_string(),
+            end_time: "20000000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
@@ -1649,28 +5148,59 @@ mod tests {
         }]);
         let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
 
-        // Attempt to cancel the market by non-admin
-        let cancel_msg = ExecuteMsg::CancelMarket { market_id: 1 };
-        let info = mock_info(USER1, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, cancel_msg);
-        assert!(res.is_err());
+        // Place a back order
+        let back_order_msg = ExecuteMsg::PlaceOrder { 
+            market_id: 1,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Back,
+            amount: Uint128::new(1000),
+            odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);
+        let _ = execute(deps.as_mut(), mock_env(), info, back_order_msg).unwrap();
+
+        // Place a matching lay order (full match)
+        let lay_order_msg = ExecuteMsg::PlaceOrder { 
+            market_id: 1,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Lay,
+            amount: Uint128::new(1000),
+            odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let info = mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(500) }]);
+        let res = execute(deps.as_mut(), mock_env(), info, lay_order_msg).unwrap();
+        
+        // Check that the orders were matched
+        assert!(res.attributes.iter().any(|attr| attr.key == "matched_amount" && attr.value == "1000"));
+
+        // Query matched bets
+        let res: Vec<MatchedBet> = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::MatchedBets { market_id: Some(1), user: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].amount, Uint128::new(1000));
     }
 
     #[test]
-    fn test_place_valid_order() {
+    fn test_cancel_order() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
 
-        // First, create a market
+        // Create a market and place an order
         let create_msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
             description: "World Cup Final".to_string(),
             options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: "100000000".to_string(),
-            end_time: "10000000000".to_string(),
+            start_time: "1000000".to_string(),
+            end_time: "20000000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
@@ -1678,7 +5208,6 @@ mod tests {
         }]);
         let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
 
-        // Now, place an order
         let place_order_msg = ExecuteMsg::PlaceOrder { 
             market_id: 1,
             option_id: 0,
@@ -1686,23 +5215,30 @@ mod tests {
             side: OrderSide::Back,
             amount: Uint128::new(1000),
             odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);
-        let res = execute(deps.as_mut(), mock_env(), info, place_order_msg).unwrap();
-        assert!(res.attributes.len() > 0);
+        let _ = execute(deps.as_mut(), mock_env(), info.clone(), place_order_msg).unwrap();
 
-        // Verify that the order was placed
-        let res: Vec<Order> = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::UserOrders { user: Addr::unchecked(USER1), market_id: Some(1), start_after: None, limit: None }).unwrap()).unwrap();
-        assert_eq!(res.len(), 1);
-        assert_eq!(res[0].amount, Uint128::new(1000));
-        assert_eq!(res[0].odds, 150);
+        // Cancel the order
+        let cancel_order_msg = ExecuteMsg::CancelOrder { order_id: 1 };
+        let res = execute(deps.as_mut(), mock_env(), info, cancel_order_msg).unwrap();
+        
+        // Check that the order was canceled
+        assert!(res.attributes.iter().any(|attr| attr.key == "method" && attr.value == "cancel_order"));
+
+        // Query the order to verify its status
+        let res: Order = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Order { order_id: 1 }).unwrap()).unwrap();
+        assert_eq!(res.status, OrderStatus::Canceled);
     }
 
     #[test]
-    fn test_place_order_insufficient_funds() {
+    fn test_propose_result_closed_market() {
         let mut deps = mock_dependencies();
+        let mut env = mock_env();
         setup_contract(deps.as_mut());
-
+    
         // Create a market
         let create_msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
@@ -1713,33 +5249,126 @@ mod tests {
             end_time: "2000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let info = mock_info(ADMIN, &[Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: Uint128::new(500000),
+        }]);
+        let _ = execute(deps.as_mut(), env.clone(), info.clone(), create_msg).unwrap();
+    
+        // Close the market
+        env.block.time = env.block.time.plus_seconds(1001);  // Move time past end_time
+        let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
+        let _ = execute(deps.as_mut(), env.clone(), info, close_msg).unwrap();
+    
+        // Propose a result
+        let propose_msg = ExecuteMsg::ProposeResult { 
+            market_id: 1, 
+            winning_outcome: 0 
+        };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
+        let res = execute(deps.as_mut(), env, info, propose_msg).unwrap();
+        
+        assert_eq!(res.attributes.len(), 5);
+        assert_eq!(res.attributes[0].value, "propose_market_result");
+        assert_eq!(res.attributes[1].value, "1");  // market_id
+        assert_eq!(res.attributes[2].value, "0");  // proposed_result
+        assert_eq!(res.attributes[3].value, USER1);  // proposer
+        assert_eq!(res.attributes[4].value, "1000000");  // bond_amount
+    }
+
+    #[test]
+    fn test_propose_result_active_market() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        // Create an active market
+        let create_msg = ExecuteMsg::CreateMarket { 
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(), // New field
+            description: "World Cup Final".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: env.block.time.seconds().to_string(),
+            end_time: (env.block.time.seconds() + 1000).to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let info = mock_info(ADMIN, &[Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: Uint128::new(500000),
+        }]);
+        let _ = execute(deps.as_mut(), env.clone(), info, create_msg).unwrap();
+
+        // Attempt to propose a result for an active market
+        let propose_msg = ExecuteMsg::ProposeResult { 
+            market_id: 1, 
+            winning_outcome: 0 
+        };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
+        let res = execute(deps.as_mut(), env, info, propose_msg);
+        
+        // Check that the proposal was rejected
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), ContractError::MarketNotEnded {});
+    }
+
+    #[test]
+    fn test_raise_dispute() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+
+        // Create, close a market, and propose a result
+        let create_msg = ExecuteMsg::CreateMarket { 
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(), // New field
+            description: "World Cup Final match details".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: "1000000".to_string(),
+            end_time: "2000000".to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
             amount: Uint128::new(500000),
         }]);
-        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
+        let _ = execute(deps.as_mut(), env.clone(), info.clone(), create_msg).unwrap();
 
-        // Attempt to place an order with insufficient funds
-        let place_order_msg = ExecuteMsg::PlaceOrder { 
-            market_id: 1,
-            option_id: 0,
-            order_type: OrderType::Limit,
-            side: OrderSide::Back,
-            amount: Uint128::new(2000),  // Amount greater than min_bet
-            odds: 150,
+        env.block.time = env.block.time.plus_seconds(1001);  // Move time past end_time
+        let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
+        let _ = execute(deps.as_mut(), env.clone(), info, close_msg).unwrap();
+
+        let propose_msg = ExecuteMsg::ProposeResult { market_id: 1, winning_outcome: 0 };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
+        let _ = execute(deps.as_mut(), env.clone(), info, propose_msg).unwrap();
+
+        // Raise a dispute
+        let dispute_msg = ExecuteMsg::RaiseDispute { 
+            market_id: 1, 
+            proposed_outcome: 1,
+            evidence: "Evidence for Team B winning".to_string()
         };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);  // Insufficient funds
-        let res = execute(deps.as_mut(), mock_env(), info, place_order_msg);
-        assert!(res.is_err());
+        let info = mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
+        let res = execute(deps.as_mut(), env, info, dispute_msg).unwrap();
+        
+        assert_eq!(res.attributes.len(), 5);
+        assert_eq!(res.attributes[0].value, "raise_dispute");
+        assert_eq!(res.attributes[1].value, "1");  // market_id
+        assert_eq!(res.attributes[2].value, "1");  // proposed_outcome
     }
 
     #[test]
-    fn test_place_order_closed_market() {
+    fn test_vote_on_dispute() {
         let mut deps = mock_dependencies();
+        let mut env = mock_env();
         setup_contract(deps.as_mut());
 
-        // Create and close a market
+        // Create, close a market, propose a result, and raise a dispute
         let create_msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
@@ -1749,334 +5378,1012 @@ mod tests {
             end_time: "2000000".to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
             amount: Uint128::new(500000),
         }]);
-        let _ = execute(deps.as_mut(), mock_env(), info.clone(), create_msg).unwrap();
+        let _ = execute(deps.as_mut(), env.clone(), info.clone(), create_msg).unwrap();
 
+        env.block.time = env.block.time.plus_seconds(1001);  // Move time past end_time
         let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
-        let mut env = mock_env();
-        env.block.time = Timestamp::from_seconds(2000001);  // Set time after end_time
-        let _ = execute(deps.as_mut(), env.clone(), info, close_msg).unwrap();
+        let _ = execute(deps.as_mut(), env.clone(), info.clone(), close_msg).unwrap();
 
-        // Attempt to place an order on the closed market
-        let place_order_msg = ExecuteMsg::PlaceOrder { 
-            market_id: 1,
-            option_id: 0,
-            order_type: OrderType::Limit,
-            side: OrderSide::Back,
-            amount: Uint128::new(1000),
-            odds: 150,
+        let propose_msg = ExecuteMsg::ProposeResult { market_id: 1, winning_outcome: 0 };
+        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
+        let _ = execute(deps.as_mut(), env.clone(), info, propose_msg).unwrap();
+
+        let dispute_msg = ExecuteMsg::RaiseDispute { 
+            market_id: 1, 
+            proposed_outcome: 1,
+            evidence: "Evidence for Team B winning".to_string()
         };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);
-        let res = execute(deps.as_mut(), env, info, place_order_msg);
-        assert!(res.is_err());
-    }
+        let info = mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
+        let _ = execute(deps.as_mut(), env.clone(), info, dispute_msg).unwrap();
 
-    #[test]
-    fn test_order_matching() {
-        let mut deps = mock_dependencies();
-        setup_contract(deps.as_mut());
+        // Add USER3 to whitelist
+        let whitelist_msg = ExecuteMsg::AddToWhitelist { address: Addr::unchecked(USER3) };
+        let info = mock_info(ADMIN, &[]);
+        let _ = execute(deps.as_mut(), env.clone(), info, whitelist_msg).unwrap();
 
-        // Create a market
-        let create_msg = ExecuteMsg::CreateMarket { 
+        // Cast a vote
+        let vote_msg = ExecuteMsg::CastVote { 
+            market_id: 1, 
+            outcome: 1 
+        };
+        let info = mock_info(USER3, &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, vote_msg).unwrap();
+        
+        assert_eq!(res.attributes.len(), 4);
+        assert_eq!(res.attributes[0].value, "cast_vote");
+        assert_eq!(res.attributes[1].value, "1");  // market_id
+        assert_eq!(res.attributes[2].value, "user3");  // voter
+        assert_eq!(res.attributes[3].value, "1");  // vote
+    }
+
+    fn create_active_market(deps: DepsMut, env: Env) -> u64 {
+        let msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
             question: "Who will win the World Cup Final?".to_string(), // New field
             description: "World Cup Final".to_string(),
             options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: "1000000".to_string(),
-            end_time: "20000000000".to_string(),
+            start_time: env.block.time.seconds().to_string(),
+            end_time: (env.block.time.seconds() + 10000).to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let info = mock_info(ADMIN, &[Coin {
             denom: TOKEN_DENOM.to_string(),
             amount: Uint128::new(500000),
         }]);
-        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
+        let res = execute(deps, env, info, msg).unwrap();
+        res.attributes.iter().find(|attr| attr.key == "market_id").unwrap().value.parse().unwrap()
+    }
 
-        // Place a back order
-        let back_order_msg = ExecuteMsg::PlaceOrder { 
-            market_id: 1,
+    // Creates a market that opened shortly before the block time of `env`
+    fn create_open_market(deps: DepsMut, env: &Env) -> u64 {
+        let mut create_env = env.clone();
+        create_env.block.time = env.block.time.minus_seconds(10);
+        let msg = ExecuteMsg::CreateMarket { 
+            category: "Sports".to_string(),
+            question: "Who will win the World Cup Final?".to_string(),
+            description: "World Cup Final match details".to_string(),
+            options: vec!["Team A".to_string(), "Team B".to_string()],
+            start_time: (env.block.time.seconds() - 5).to_string(),
+            end_time: (env.block.time.seconds() + 10000).to_string(),
+            resolution_bond: Uint128::new(1000000),
+            resolution_reward: Uint128::new(500000),
+            opening_auction: None,
+        };
+        let info = mock_info(ADMIN, &[Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: Uint128::new(500000),
+        }]);
+        let res = execute(deps, create_env, info, msg).unwrap();
+        res.attributes.iter().find(|attr| attr.key == "market_id").unwrap().value.parse().unwrap()
+    }
+
+    // Closes the market, proposes `outcome` and resolves it without a dispute
+    fn resolve_market(deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>, env: &mut Env, market_id: u64, outcome: u8) {
+        env.block.time = env.block.time.plus_seconds(10001);
+        let close_msg = ExecuteMsg::CloseMarket { market_id };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), close_msg).unwrap();
+
+        let propose_msg = ExecuteMsg::ProposeResult { market_id, winning_outcome: outcome };
+        execute(deps.as_mut(), env.clone(), mock_info(USER3, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]), propose_msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(86401);
+        let resolve_msg = ExecuteMsg::ResolveDispute { market_id };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), resolve_msg).unwrap();
+    }
+
+    // Closes the market, has USER3 propose `proposed` and USER2 dispute it with `disputed`
+    fn dispute_market(deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>, env: &mut Env, market_id: u64, proposed: u8, disputed: u8) {
+        let bond = [Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }];
+        env.block.time = env.block.time.plus_seconds(10001);
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::CloseMarket { market_id }).unwrap();
+
+        let propose_msg = ExecuteMsg::ProposeResult { market_id, winning_outcome: proposed };
+        execute(deps.as_mut(), env.clone(), mock_info(USER3, &bond), propose_msg).unwrap();
+
+        let dispute_msg = ExecuteMsg::RaiseDispute { market_id, proposed_outcome: disputed, evidence: "Official match report".to_string() };
+        execute(deps.as_mut(), env.clone(), mock_info(USER2, &bond), dispute_msg).unwrap();
+    }
+
+    // Whitelists the voter and casts its vote
+    fn vote(deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>, env: &Env, voter: &str, market_id: u64, outcome: u8) {
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::AddToWhitelist { address: Addr::unchecked(voter) }).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), ExecuteMsg::CastVote { market_id, outcome }).unwrap();
+    }
+
+    // Places a limit order on option 0, attaching exactly the required collateral
+    fn place_limit_order(deps: DepsMut, env: &Env, user: &str, market_id: u64, side: OrderSide, amount: u128, odds: u32) -> Response {
+        let funds = match side {
+            OrderSide::Back => amount,
+            OrderSide::Lay => amount * (odds as u128 - 100) / 100,
+        };
+        let msg = ExecuteMsg::PlaceOrder { 
+            market_id,
             option_id: 0,
             order_type: OrderType::Limit,
+            side,
+            amount: Uint128::new(amount),
+            odds,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        execute(deps, env.clone(), mock_info(user, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(funds) }]), msg).unwrap()
+    }
+
+    fn attr(res: &Response, key: &str) -> String {
+        res.attributes.iter().find(|attr| attr.key == key).unwrap().value.clone()
+    }
+
+    fn balance(deps: Deps, user: &str) -> Uint128 {
+        from_json(query(deps, mock_env(), QueryMsg::Balance { user: Addr::unchecked(user) }).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_platform_fee_on_back_winnings() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 150);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 150);
+        resolve_market(&mut deps, &mut env, market_id, 0);
+
+        // Net profit is 500, so the 1% fee is 5
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::RedeemWinnings { matched_bet_id: 1 }).unwrap();
+        assert_eq!(attr(&res, "winnings"), "1495");
+        assert_eq!(attr(&res, "fee"), "5");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1495));
+
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::new(5));
+        assert_eq!(fees.collected, Uint128::zero());
+
+        // Only the admin can sweep
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::SweepFees { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::SweepFees { market_id }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: vec![Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(5) }],
+        }));
+
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::zero());
+        assert_eq!(fees.collected, Uint128::new(5));
+
+        // Nothing left to sweep
+        let res = execute(deps.as_mut(), env, mock_info(ADMIN, &[]), ExecuteMsg::SweepFees { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::NoPendingFees {});
+    }
+
+    #[test]
+    fn test_platform_fee_on_lay_winnings() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 150);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 150);
+        resolve_market(&mut deps, &mut env, market_id, 1);
+
+        // The layer gets the whole pot back, minus 1% of the 1000 back stake they won
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::RedeemWinnings { matched_bet_id: 1 }).unwrap();
+        assert_eq!(attr(&res, "winnings"), "1490");
+        assert_eq!(attr(&res, "fee"), "10");
+    }
+
+    #[test]
+    fn test_platform_fee_fixed_per_market() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+
+        // bettor1 hedges its back bet completely, its collateral is released at the 1% fee
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, "bettor1", market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, "bettor2", market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, "bettor1", market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, "bettor3", market_id, OrderSide::Back, 1000, 200);
+        assert_eq!(balance(deps.as_ref(), "bettor1"), Uint128::new(1990));
+
+        // Raising the fee afterwards leaves the market on the rate it was created with
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate { platform_fee: Some(Uint128::new(1000)), ..ConfigUpdate::default() });
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        resolve_market(&mut deps, &mut env, market_id, 0);
+
+        // The fees of both bets accrue on resolution, bettor1 has nothing left to redeem on its hedged bets
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::new(20));
+        execute(deps.as_mut(), env.clone(), mock_info("bettor3", &[]), ExecuteMsg::RedeemMarket { market_id }).unwrap();
+
+        // Balances and fees add up to the 4000 deposited
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id }).unwrap()).unwrap();
+        let balances: Uint128 = ["bettor1", "bettor2", "bettor3"].iter().map(|bettor| balance(deps.as_ref(), bettor)).sum();
+        assert_eq!(balances + fees.pending, Uint128::new(4000));
+    }
+
+    #[test]
+    fn test_platform_fee_validation() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            platform_fee: Some(Uint128::new(10001)),
+            ..ConfigUpdate::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidPlatformFee {});
+    }
+
+    #[test]
+    fn test_market_order_sweeps_book_and_refunds_remainder() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 180);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Lay, 1000, 200);
+
+        let msg = ExecuteMsg::PlaceOrder { 
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Market { worst_odds: None },
             side: OrderSide::Back,
-            amount: Uint128::new(1000),
-            odds: 150,
+            amount: Uint128::new(3000),
+            odds: 0,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);
-        let _ = execute(deps.as_mut(), mock_env(), info, back_order_msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(3000) }]), msg).unwrap();
+        assert_eq!(attr(&res, "matched_amount"), "2000");
+        assert_eq!(attr(&res, "locked_amount"), "2000");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1000));
+
+        // The best odds for the backer are taken first
+        let bets: Vec<MatchedBet> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MatchedBets { market_id: Some(market_id), user: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(bets.len(), 2);
+        assert_eq!(bets[0].odds, 200);
+        assert_eq!(bets[0].lay_user, Addr::unchecked(USER3));
+        assert_eq!(bets[1].odds, 180);
+
+        // The unmatched remainder does not rest on the book
+        let order: Order = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Order { order_id: 3 }).unwrap()).unwrap();
+        assert_eq!(order.status, OrderStatus::Canceled);
+        assert_eq!(order.amount, Uint128::new(2000));
+        assert_eq!(order.filled_amount, Uint128::new(2000));
+        let book: Vec<Order> = from_json(query(deps.as_ref(), env, QueryMsg::MarketOrders { market_id, side: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert!(book.is_empty());
+    }
 
-        // Place a matching lay order (full match)
-        let lay_order_msg = ExecuteMsg::PlaceOrder { 
-            market_id: 1,
+    #[test]
+    fn test_market_order_respects_worst_odds() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Back, 1000, 250);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Back, 1000, 150);
+
+        // Only the back order at 1.5 is within the bound, its liability is 500
+        let msg = ExecuteMsg::PlaceOrder { 
+            market_id,
             option_id: 0,
-            order_type: OrderType::Limit,
+            order_type: OrderType::Market { worst_odds: Some(200) },
+            side: OrderSide::Lay,
+            amount: Uint128::new(2000),
+            odds: 0,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]), msg).unwrap();
+        assert_eq!(attr(&res, "matched_amount"), "1000");
+        assert_eq!(attr(&res, "locked_amount"), "500");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(500));
+
+        let book: Vec<Order> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketOrders { market_id, side: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(book.len(), 1);
+        assert_eq!(book[0].odds, 250);
+
+        // Not enough collateral for what would be matched
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Back, 1000, 150);
+        let msg = ExecuteMsg::PlaceOrder { 
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Market { worst_odds: None },
             side: OrderSide::Lay,
             amount: Uint128::new(1000),
-            odds: 150,
+            odds: 0,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
-        let info = mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(500) }]);
-        let res = execute(deps.as_mut(), mock_env(), info, lay_order_msg).unwrap();
-        
-        // Check that the orders were matched
-        assert!(res.attributes.iter().any(|attr| attr.key == "matched_amount" && attr.value == "1000"));
+        let res = execute(deps.as_mut(), env, mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(100) }]), msg);
+        assert_eq!(res.unwrap_err(), ContractError::InsufficientFunds {});
+    }
 
-        // Query matched bets
-        let res: Vec<MatchedBet> = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::MatchedBets { market_id: Some(1), user: None, start_after: None, limit: None }).unwrap()).unwrap();
-        assert_eq!(res.len(), 1);
-        assert_eq!(res[0].amount, Uint128::new(1000));
+    fn order_with_time_in_force(market_id: u64, side: OrderSide, amount: u128, odds: u32, time_in_force: TimeInForce) -> ExecuteMsg {
+        ExecuteMsg::PlaceOrder { 
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side,
+            amount: Uint128::new(amount),
+            odds,
+            time_in_force: Some(time_in_force),
+            keep_on_suspend: None,
+        }
     }
 
     #[test]
-    fn test_cancel_order() {
+    fn test_fill_or_kill_order() {
         let mut deps = mock_dependencies();
+        let env = mock_env();
         setup_contract(deps.as_mut());
 
-        // Create a market and place an order
-        let create_msg = ExecuteMsg::CreateMarket { 
-            category: "Sports".to_string(),
-            question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final".to_string(),
-            options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: "1000000".to_string(),
-            end_time: "20000000000".to_string(),
-            resolution_bond: Uint128::new(1000000),
-            resolution_reward: Uint128::new(500000),
-        };
-        let info = mock_info(ADMIN, &[Coin {
-            denom: TOKEN_DENOM.to_string(),
-            amount: Uint128::new(500000),
-        }]);
-        let _ = execute(deps.as_mut(), mock_env(), info, create_msg).unwrap();
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let funds = [Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }];
 
-        let place_order_msg = ExecuteMsg::PlaceOrder { 
-            market_id: 1,
+        // Fully matched
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+        let msg = order_with_time_in_force(market_id, OrderSide::Back, 1000, 200, TimeInForce::FillOrKill);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &funds), msg).unwrap();
+        assert_eq!(attr(&res, "matched_amount"), "1000");
+
+        // Only half of it can be matched
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+        let msg = order_with_time_in_force(market_id, OrderSide::Back, 2000, 200, TimeInForce::FillOrKill);
+        let res = execute(deps.as_mut(), env, mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(2000) }]), msg);
+        assert_eq!(res.unwrap_err(), ContractError::OrderNotFilled {});
+    }
+
+    #[test]
+    fn test_post_only_order() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let funds = [Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }];
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+
+        // Backing at 1.5 would take the lay at 2.0
+        let msg = order_with_time_in_force(market_id, OrderSide::Back, 1000, 150, TimeInForce::PostOnly);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &funds), msg);
+        assert_eq!(res.unwrap_err(), ContractError::PostOnlyWouldMatch {});
+
+        // Backing at 2.5 does not cross and rests
+        let msg = order_with_time_in_force(market_id, OrderSide::Back, 1000, 250, TimeInForce::PostOnly);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &funds), msg).unwrap();
+        assert_eq!(attr(&res, "matched_amount"), "0");
+
+        // Post-only makes no sense for market orders
+        let msg = ExecuteMsg::PlaceOrder { 
+            market_id,
             option_id: 0,
-            order_type: OrderType::Limit,
+            order_type: OrderType::Market { worst_odds: None },
             side: OrderSide::Back,
             amount: Uint128::new(1000),
-            odds: 150,
+            odds: 0,
+            time_in_force: Some(TimeInForce::PostOnly),
+            keep_on_suspend: None,
         };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]);
-        let _ = execute(deps.as_mut(), mock_env(), info.clone(), place_order_msg).unwrap();
+        let res = execute(deps.as_mut(), env, mock_info(USER1, &funds), msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidTimeInForce {});
+    }
 
-        // Cancel the order
-        let cancel_order_msg = ExecuteMsg::CancelOrder { order_id: 1 };
-        let res = execute(deps.as_mut(), mock_env(), info, cancel_order_msg).unwrap();
-        
-        // Check that the order was canceled
-        assert!(res.attributes.iter().any(|attr| attr.key == "method" && attr.value == "cancel_order"));
+    #[test]
+    fn test_good_til_date_order() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
 
-        // Query the order to verify its status
-        let res: Order = from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Order { order_id: 1 }).unwrap()).unwrap();
-        assert_eq!(res.status, OrderStatus::Canceled);
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let funds = [Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }];
+        let expires_at = env.block.time.seconds() + 100;
+
+        // Expiry must be in the future
+        let msg = order_with_time_in_force(market_id, OrderSide::Back, 1000, 200, TimeInForce::GoodTilDate { expires_at: env.block.time.seconds() });
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &funds), msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidExpiry {});
+
+        let msg = order_with_time_in_force(market_id, OrderSide::Back, 1000, 200, TimeInForce::GoodTilDate { expires_at });
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &funds), msg).unwrap();
+
+        let book: Vec<Order> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketOrders { market_id, side: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(book.len(), 1);
+
+        // Once expired the order is hidden from the book and never matched
+        env.block.time = env.block.time.plus_seconds(100);
+        let book: Vec<Order> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketOrders { market_id, side: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert!(book.is_empty());
+        let res = place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+        assert_eq!(attr(&res, "matched_amount"), "0");
+
+        // Anyone can cancel an expired order, the refund goes to its creator
+        let res = execute(deps.as_mut(), env, mock_info(USER3, &[]), ExecuteMsg::CancelOrder { order_id: 1 }).unwrap();
+        assert_eq!(attr(&res, "refund_amount"), "1000");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1000));
+        assert!(balance(deps.as_ref(), USER3).is_zero());
     }
 
     #[test]
-    fn test_propose_result_closed_market() {
+    fn test_order_book_price_time_priority() {
         let mut deps = mock_dependencies();
-        let mut env = mock_env();
+        let env = mock_env();
         setup_contract(deps.as_mut());
-    
-        // Create a market
-        let create_msg = ExecuteMsg::CreateMarket { 
-            category: "Sports".to_string(),
-            question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final match details".to_string(),
-            options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: "1000000".to_string(),
-            end_time: "2000000".to_string(),
-            resolution_bond: Uint128::new(1000000),
-            resolution_reward: Uint128::new(500000),
-        };
-        let info = mock_info(ADMIN, &[Coin {
-            denom: TOKEN_DENOM.to_string(),
-            amount: Uint128::new(500000),
-        }]);
-        let _ = execute(deps.as_mut(), env.clone(), info.clone(), create_msg).unwrap();
-    
-        // Close the market
-        env.block.time = env.block.time.plus_seconds(1001);  // Move time past end_time
-        let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
-        let _ = execute(deps.as_mut(), env.clone(), info, close_msg).unwrap();
-    
-        // Propose a result
-        let propose_msg = ExecuteMsg::ProposeResult { 
-            market_id: 1, 
-            winning_outcome: 0 
-        };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
-        let res = execute(deps.as_mut(), env, info, propose_msg).unwrap();
-        
-        assert_eq!(res.attributes.len(), 5);
-        assert_eq!(res.attributes[0].value, "propose_market_result");
-        assert_eq!(res.attributes[1].value, "1");  // market_id
-        assert_eq!(res.attributes[2].value, "0");  // proposed_result
-        assert_eq!(res.attributes[3].value, USER1);  // proposer
-        assert_eq!(res.attributes[4].value, "1000000");  // bond_amount
+
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let other_market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 180);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER3, other_market_id, OrderSide::Lay, 1000, 300);
+
+        // Both orders at 2.0 are taken before the one at 1.8, the earliest one first
+        let res = place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1500, 150);
+        assert_eq!(attr(&res, "matched_amount"), "1500");
+        let bets: Vec<MatchedBet> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MatchedBets { market_id: Some(market_id), user: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(bets.len(), 2);
+        assert_eq!((bets[0].lay_user.as_str(), bets[0].odds, bets[0].amount), (USER2, 200, Uint128::new(1000)));
+        assert_eq!((bets[1].lay_user.as_str(), bets[1].odds, bets[1].amount), (USER3, 200, Uint128::new(500)));
+
+        // Only the partially filled order and the one at 1.8 remain on the book
+        let book: Vec<u64> = resting_market_orders(deps.as_ref().storage, market_id).unwrap().iter().map(|order| order.id).collect();
+        assert_eq!(book, vec![3, 1]);
+
+        let stats: MarketStatistics = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketStatistics { market_id }).unwrap()).unwrap();
+        assert_eq!(stats.order_count, 4);
+        assert_eq!(stats.total_volume, Uint128::new(1500));
+
+        // Closing the market empties its book but leaves the other market alone
+        let mut close_env = env.clone();
+        close_env.block.time = env.block.time.plus_seconds(10001);
+        execute(deps.as_mut(), close_env, mock_info(ADMIN, &[]), ExecuteMsg::CloseMarket { market_id }).unwrap();
+        assert!(resting_market_orders(deps.as_ref().storage, market_id).unwrap().is_empty());
+        assert_eq!(resting_market_orders(deps.as_ref().storage, other_market_id).unwrap().len(), 1);
     }
 
     #[test]
-    fn test_propose_result_active_market() {
+    fn test_user_orders_and_matched_bets_indexes() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         setup_contract(deps.as_mut());
 
-        // Create an active market
-        let create_msg = ExecuteMsg::CreateMarket { 
-            category: "Sports".to_string(),
-            question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final".to_string(),
-            options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: env.block.time.seconds().to_string(),
-            end_time: (env.block.time.seconds() + 1000).to_string(),
-            resolution_bond: Uint128::new(1000000),
-            resolution_reward: Uint128::new(500000),
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let other_market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER1, other_market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 300);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+
+        let user_orders = |market_id: Option<u64>, status: Option<&str>, start_after: Option<u64>| -> Vec<u64> {
+            let orders: Vec<Order> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::UserOrders {
+                user: Addr::unchecked(USER1),
+                market_id,
+                status: status.map(|s| s.to_string()),
+                start_after,
+                limit: Some(1),
+            }).unwrap()).unwrap();
+            orders.iter().map(|order| order.id).collect()
         };
-        let info = mock_info(ADMIN, &[Coin {
-            denom: TOKEN_DENOM.to_string(),
-            amount: Uint128::new(500000),
-        }]);
-        let _ = execute(deps.as_mut(), env.clone(), info, create_msg).unwrap();
+        assert_eq!(user_orders(None, None, None), vec![1]);
+        assert_eq!(user_orders(None, None, Some(1)), vec![2]);
+        assert_eq!(user_orders(Some(market_id), None, Some(1)), vec![3]);
+        assert_eq!(user_orders(Some(market_id), Some("Open"), None), vec![3]);
+        assert_eq!(user_orders(Some(market_id), Some("Filled"), None), vec![1]);
+        assert_eq!(user_orders(Some(other_market_id), Some("Filled"), None), Vec::<u64>::new());
+
+        // Both sides of a matched bet find it through their own index
+        for user in [USER1, USER2] {
+            let bets: Vec<MatchedBet> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MatchedBets { market_id: Some(market_id), user: Some(Addr::unchecked(user)), start_after: None, limit: None }).unwrap()).unwrap();
+            assert_eq!(bets.len(), 1);
+            assert_eq!((bets[0].back_user.as_str(), bets[0].lay_user.as_str()), (USER1, USER2));
+        }
+        let bets: Vec<MatchedBet> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MatchedBets { market_id: Some(other_market_id), user: Some(Addr::unchecked(USER1)), start_after: None, limit: None }).unwrap()).unwrap();
+        assert!(bets.is_empty());
+        assert!(USER_MATCHED_BETS.prefix(Addr::unchecked(USER3)).keys(deps.as_ref().storage, None, None, cosmwasm_std::Order::Ascending).next().is_none());
+    }
 
-        // Attempt to propose a result for an active market
-        let propose_msg = ExecuteMsg::ProposeResult { 
-            market_id: 1, 
-            winning_outcome: 0 
+    #[test]
+    fn test_order_book_depth() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 250);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 2000, 250);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Back, 1000, 300);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Back, 1000, 400);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 180);
+        let expires_at = env.block.time.seconds() + 100;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1100) }]),
+            order_with_time_in_force(market_id, OrderSide::Lay, 1000, 210, TimeInForce::GoodTilDate { expires_at }),
+        ).unwrap();
+
+        let order_book = |deps: Deps, env: &Env, depth: Option<u32>| -> OrderBook {
+            from_json(query(deps, env.clone(), QueryMsg::OrderBook { market_id, option_id: 0, depth }).unwrap()).unwrap()
         };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
-        let res = execute(deps.as_mut(), env, info, propose_msg);
-        
-        // Check that the proposal was rejected
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), ContractError::MarketNotEnded {});
+        let book = order_book(deps.as_ref(), &env, Some(2));
+        assert_eq!(book.back, vec![
+            PriceLevel { odds: 250, amount: Uint128::new(3000), order_count: 2 },
+            PriceLevel { odds: 300, amount: Uint128::new(1000), order_count: 1 },
+        ]);
+        assert_eq!(book.lay, vec![
+            PriceLevel { odds: 210, amount: Uint128::new(1000), order_count: 1 },
+            PriceLevel { odds: 200, amount: Uint128::new(1000), order_count: 1 },
+        ]);
+        assert_eq!((book.best_back, book.best_lay, book.last_matched_odds), (Some(250), Some(210), None));
+
+        // A lay at 2.5 takes part of the best back level and sets the last matched odds
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1500, 250);
+        let book = order_book(deps.as_ref(), &env, None);
+        assert_eq!(book.back.len(), 3);
+        assert_eq!(book.back[0], PriceLevel { odds: 250, amount: Uint128::new(1500), order_count: 1 });
+        assert_eq!(book.last_matched_odds, Some(250));
+
+        // Expired orders are left out of the depth
+        let mut later = env.clone();
+        later.block.time = env.block.time.plus_seconds(100);
+        let book = order_book(deps.as_ref(), &later, None);
+        assert_eq!(book.best_lay, Some(200));
+        assert_eq!(book.lay.len(), 2);
     }
 
     #[test]
-    fn test_raise_dispute() {
+    fn test_redeem_market_and_redeem_all() {
         let mut deps = mock_dependencies();
         let mut env = mock_env();
         setup_contract(deps.as_mut());
 
-        // Create, close a market, and propose a result
-        let create_msg = ExecuteMsg::CreateMarket { 
-            category: "Sports".to_string(),
-            question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final match details".to_string(),
-            options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: "1000000".to_string(),
-            end_time: "2000000".to_string(),
-            resolution_bond: Uint128::new(1000000),
-            resolution_reward: Uint128::new(500000),
-        };
-        let info = mock_info(ADMIN, &[Coin {
-            denom: TOKEN_DENOM.to_string(),
-            amount: Uint128::new(500000),
-        }]);
-        let _ = execute(deps.as_mut(), env.clone(), info.clone(), create_msg).unwrap();
-
-        env.block.time = env.block.time.plus_seconds(1001);  // Move time past end_time
-        let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
-        let _ = execute(deps.as_mut(), env.clone(), info, close_msg).unwrap();
+        // USER1 backs option 0 three times in the first market and once in the second
+        let market_id = create_open_market(deps.as_mut(), &env);
+        let other_market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 3000, 150);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 150);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 150);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 150);
+        place_limit_order(deps.as_mut(), &env, USER2, other_market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER1, other_market_id, OrderSide::Back, 1000, 200);
+
+        let mut resolve_env = env.clone();
+        resolve_market(&mut deps, &mut resolve_env, market_id, 0);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::RedeemMarket { market_id: other_market_id });
+        assert_eq!(res.unwrap_err(), ContractError::MarketNotResolved {});
+
+        // The layer lost every bet of the first market
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::RedeemMarket { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::NothingToRedeem {});
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::RedeemMarket { market_id }).unwrap();
+        let redeemed: Vec<&str> = res.attributes.iter().filter(|attr| attr.key == "redeemed").map(|attr| attr.value.as_str()).collect();
+        assert_eq!(redeemed, vec!["1:1495", "2:1495", "3:1495"]);
+        assert_eq!(attr(&res, "winnings"), "4485");
+        assert_eq!(attr(&res, "fee"), "15");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(4485));
+        let fees: MarketFees = from_json(query(deps.as_ref(), env.clone(), QueryMsg::MarketFees { market_id }).unwrap()).unwrap();
+        assert_eq!(fees.pending, Uint128::new(15));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::RedeemMarket { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::NothingToRedeem {});
+
+        // The second market is resolved for the layer, who redeems page by page
+        env.block.time = resolve_env.block.time;
+        resolve_market(&mut deps, &mut env, other_market_id, 1);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::RedeemAll { start_after: None, limit: Some(3) }).unwrap();
+        assert_eq!(attr(&res, "redeemed_count"), "0");
+        assert_eq!(attr(&res, "last_matched_bet_id"), "3");
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::RedeemAll { start_after: Some(3), limit: Some(3) }).unwrap();
+        assert_eq!(attr(&res, "redeemed"), "4:1990");
+        assert_eq!(attr(&res, "winnings"), "1990");
+        assert_eq!(balance(deps.as_ref(), USER2), Uint128::new(1990));
+
+        let res = execute(deps.as_mut(), env, mock_info(USER1, &[]), ExecuteMsg::RedeemAll { start_after: None, limit: None }).unwrap();
+        assert_eq!(attr(&res, "redeemed_count"), "0");
+    }
 
-        let propose_msg = ExecuteMsg::ProposeResult { market_id: 1, winning_outcome: 0 };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
-        let _ = execute(deps.as_mut(), env.clone(), info, propose_msg).unwrap();
+    // USER1 backs option 0 at 2.0 against USER2 and then lays it at 2.0 against USER3
+    fn hedged_market(deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>, env: &Env) -> u64 {
+        let market_id = create_open_market(deps.as_mut(), env);
+        place_limit_order(deps.as_mut(), env, USER2, market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), env, USER1, market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), env, USER3, market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), env, USER1, market_id, OrderSide::Lay, 1000, 200);
+        market_id
+    }
 
-        // Raise a dispute
-        let dispute_msg = ExecuteMsg::RaiseDispute { 
-            market_id: 1, 
-            proposed_outcome: 1,
-            evidence: "Evidence for Team B winning".to_string()
-        };
-        let info = mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
-        let res = execute(deps.as_mut(), env, info, dispute_msg).unwrap();
-        
-        assert_eq!(res.attributes.len(), 5);
-        assert_eq!(res.attributes[0].value, "raise_dispute");
-        assert_eq!(res.attributes[1].value, "1");  // market_id
-        assert_eq!(res.attributes[2].value, "1");  // proposed_outcome
+    #[test]
+    fn test_hedged_position_releases_collateral() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+        let market_id = hedged_market(&mut deps, &env);
+
+        // Only the 1% fee on either bet's profit is still at risk
+        let res: PositionResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Position { user: Addr::unchecked(USER1), market_id, option_id: 0 }).unwrap()).unwrap();
+        assert_eq!(res.position.collateral(), Uint128::new(2000));
+        assert_eq!(res.position.released, Uint128::new(1990));
+        assert_eq!((res.pnl_if_win, res.pnl_if_lose), (Int128::new(-10), Int128::new(-10)));
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1990));
+
+        // One-sided positions keep all of their collateral locked
+        let res: PositionsResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Positions { user: Addr::unchecked(USER2), market_id }).unwrap()).unwrap();
+        assert_eq!(res.positions[0].position.released, Uint128::zero());
+        assert_eq!(res.outcome_pnl, vec![Int128::new(-1000), Int128::new(990)]);
+        let res: PositionsResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Positions { user: Addr::unchecked(USER1), market_id }).unwrap()).unwrap();
+        assert_eq!(res.outcome_pnl, vec![Int128::new(-10), Int128::new(-10)]);
+
+        // The released collateral is deducted from the winnings of the back bet
+        resolve_market(&mut deps, &mut env, market_id, 0);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::RedeemWinnings { matched_bet_id: 1 }).unwrap();
+        assert_eq!(attr(&res, "winnings"), "0");
+        assert_eq!(attr(&res, "fee"), "10");
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::RedeemWinnings { matched_bet_id: 2 }).unwrap();
+        assert_eq!(attr(&res, "winnings"), "1990");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1990));
+
+        // Cancelling instead refunds the collateral still held back
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let market_id = hedged_market(&mut deps, &env);
+        execute(deps.as_mut(), env, mock_info(ADMIN, &[]), ExecuteMsg::CancelMarket { market_id }).unwrap();
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(2000));
+        assert_eq!(balance(deps.as_ref(), USER2), Uint128::new(1000));
+        assert_eq!(balance(deps.as_ref(), USER3), Uint128::new(1000));
     }
 
     #[test]
-    fn test_vote_on_dispute() {
+    fn test_cash_out() {
         let mut deps = mock_dependencies();
         let mut env = mock_env();
         setup_contract(deps.as_mut());
 
-        // Create, close a market, propose a result, and raise a dispute
-        let create_msg = ExecuteMsg::CreateMarket { 
-            category: "Sports".to_string(),
-            question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final match details".to_string(),
-            options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: "1000000".to_string(),
-            end_time: "2000000".to_string(),
-            resolution_bond: Uint128::new(1000000),
-            resolution_reward: Uint128::new(500000),
-        };
-        let info = mock_info(ADMIN, &[Coin {
-            denom: TOKEN_DENOM.to_string(),
-            amount: Uint128::new(500000),
-        }]);
-        let _ = execute(deps.as_mut(), env.clone(), info.clone(), create_msg).unwrap();
+        // USER1 backs at 3.0, then the odds shorten to 2.0
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 300);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 300);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Back, 1000, 250);
 
-        env.block.time = env.block.time.plus_seconds(1001);  // Move time past end_time
-        let close_msg = ExecuteMsg::CloseMarket { market_id: 1 };
-        let _ = execute(deps.as_mut(), env.clone(), info.clone(), close_msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::CashOut { market_id, option_id: 1, worst_odds: None });
+        assert_eq!(res.unwrap_err(), ContractError::CashOutUnavailable {});
 
-        let propose_msg = ExecuteMsg::ProposeResult { market_id: 1, winning_outcome: 0 };
-        let info = mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
-        let _ = execute(deps.as_mut(), env.clone(), info, propose_msg).unwrap();
+        // Laying 1500 at 2.0 locks in 500 on both outcomes
+        let quote: CashOutQuote = from_json(query(deps.as_ref(), env.clone(), QueryMsg::CashOutQuote { user: Addr::unchecked(USER1), market_id, option_id: 0 }).unwrap()).unwrap();
+        assert_eq!(quote.side, Some(OrderSide::Lay));
+        assert_eq!((quote.amount, quote.odds, quote.available), (Uint128::new(1500), 200, Uint128::new(1000)));
+        assert_eq!(quote.profit, Int128::new(500));
 
-        let dispute_msg = ExecuteMsg::RaiseDispute { 
-            market_id: 1, 
-            proposed_outcome: 1,
-            evidence: "Evidence for Team B winning".to_string()
+        // Only 1000 is available at 2.0, so the hedge cannot fill without slippage
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CashOut { market_id, option_id: 0, worst_odds: None });
+        assert_eq!(res.unwrap_err(), ContractError::OrderNotFilled {});
+
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let market_id = create_open_market(deps.as_mut(), &env);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 300);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 300);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Back, 2000, 200);
+
+        // The hedge is funded by the collateral it releases
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CashOut { market_id, option_id: 0, worst_odds: Some(210) }).unwrap();
+        assert_eq!(attr(&res, "method"), "cash_out");
+        assert_eq!(attr(&res, "matched_amount"), "1500");
+        assert_eq!(attr(&res, "quoted_profit"), "500");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1000));
+
+        let position: PositionResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Position { user: Addr::unchecked(USER1), market_id, option_id: 0 }).unwrap()).unwrap();
+        assert_eq!((position.pnl_if_win, position.pnl_if_lose), (Int128::new(480), Int128::new(485)));
+        let quote: CashOutQuote = from_json(query(deps.as_ref(), env.clone(), QueryMsg::CashOutQuote { user: Addr::unchecked(USER1), market_id, option_id: 0 }).unwrap()).unwrap();
+        assert_eq!(quote.side, None);
+
+        resolve_market(&mut deps, &mut env, market_id, 0);
+        execute(deps.as_mut(), env, mock_info(USER1, &[]), ExecuteMsg::RedeemWinnings { matched_bet_id: 1 }).unwrap();
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1480));
+    }
+
+    #[test]
+    fn test_option_validation_and_status() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+        let market_id = create_open_market(deps.as_mut(), &env);
+        for user in [USER1, USER2] {
+            execute(deps.as_mut(), env.clone(), mock_info(user, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(10000) }]), ExecuteMsg::Deposit {}).unwrap();
+        }
+        let order = |option_id: u8, side: OrderSide, odds: u32| ExecuteMsg::PlaceOrder {
+            market_id,
+            option_id,
+            order_type: OrderType::Limit,
+            side,
+            amount: Uint128::new(1000),
+            odds,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
-        let info = mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }]);
-        let _ = execute(deps.as_mut(), env.clone(), info, dispute_msg).unwrap();
 
-        // Add USER3 to whitelist
-        let whitelist_msg = ExecuteMsg::AddToWhitelist { address: Addr::unchecked(USER3) };
-        let info = mock_info(ADMIN, &[]);
-        let _ = execute(deps.as_mut(), env.clone(), info, whitelist_msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), order(2, OrderSide::Back, 200));
+        assert_eq!(res.unwrap_err(), ContractError::InvalidOption {});
 
-        // Cast a vote
-        let vote_msg = ExecuteMsg::CastVote { 
-            market_id: 1, 
-            outcome: 1 
+        for option_id in [0, 1] {
+            execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), order(option_id, OrderSide::Back, 200)).unwrap();
+            execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), order(option_id, OrderSide::Lay, 200)).unwrap();
+        }
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), order(1, OrderSide::Back, 300)).unwrap();
+
+        // Suspending stops trading on the option until it is resumed
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::SuspendOption { market_id, option_id: 1 });
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::SuspendOption { market_id, option_id: 1 }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), order(1, OrderSide::Lay, 300));
+        assert_eq!(res.unwrap_err(), ContractError::OptionNotActive {});
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResumeOption { market_id, option_id: 1 }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResumeOption { market_id, option_id: 1 });
+        assert_eq!(res.unwrap_err(), ContractError::OptionNotSuspended {});
+
+        // Voiding refunds the option's matched bet and resting order, option 0 is untouched
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::VoidOption { market_id, option_id: 1 }).unwrap();
+        assert_eq!(attr(&res, "refunded_bets"), "1");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(9000));
+        assert_eq!(balance(deps.as_ref(), USER2), Uint128::new(9000));
+        let resting_order: Order = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Order { order_id: 5 }).unwrap()).unwrap();
+        assert_eq!(resting_order.status, OrderStatus::Canceled);
+        let bet: MatchedBet = from_json::<Vec<MatchedBet>>(query(deps.as_ref(), env.clone(), QueryMsg::MatchedBets { market_id: Some(market_id), user: None, start_after: None, limit: None }).unwrap()).unwrap().remove(0);
+        assert!(!bet.redeemed);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), order(1, OrderSide::Lay, 300));
+        assert_eq!(res.unwrap_err(), ContractError::OptionNotActive {});
+
+        // A void or nonexistent option cannot be proposed as the result
+        env.block.time = env.block.time.plus_seconds(10001);
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::CloseMarket { market_id }).unwrap();
+        let bond = [Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000000) }];
+        for outcome in [1, 2] {
+            let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &bond), ExecuteMsg::ProposeResult { market_id, winning_outcome: outcome });
+            assert_eq!(res.unwrap_err(), ContractError::InvalidOption {});
+        }
+        execute(deps.as_mut(), env, mock_info(USER3, &bond), ExecuteMsg::ProposeResult { market_id, winning_outcome: 0 }).unwrap();
+    }
+
+    #[test]
+    fn test_suspend_and_resume_market() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let market_id = create_open_market(deps.as_mut(), &env);
+
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 200);
+        let msg = ExecuteMsg::PlaceOrder {
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Lay,
+            amount: Uint128::new(1000),
+            odds: 150,
+            time_in_force: None,
+            keep_on_suspend: Some(true),
         };
-        let info = mock_info(USER3, &[]);
-        let res = execute(deps.as_mut(), env.clone(), info, vote_msg).unwrap();
-        
-        assert_eq!(res.attributes.len(), 4);
-        assert_eq!(res.attributes[0].value, "cast_vote");
-        assert_eq!(res.attributes[1].value, "1");  // market_id
-        assert_eq!(res.attributes[2].value, "user3");  // voter
-        assert_eq!(res.attributes[3].value, "1");  // vote
+        execute(deps.as_mut(), env.clone(), mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(500) }]), msg).unwrap();
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::SuspendMarket { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+        // Only the order without keep-on-suspend is cancelled and refunded
+        let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::SuspendMarket { market_id }).unwrap();
+        assert_eq!(attr(&res, "canceled_orders"), "1");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1000));
+        let book: Vec<u64> = resting_market_orders(deps.as_ref().storage, market_id).unwrap().iter().map(|order| order.id).collect();
+        assert_eq!(book, vec![2]);
+        let market: Market = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Market { market_id }).unwrap()).unwrap();
+        assert_eq!(market.status, MarketStatus::Suspended);
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CancelOrder { order_id: 1 });
+        assert_eq!(res.unwrap_err(), ContractError::OrderNotCancellable {});
+        let msg = ExecuteMsg::PlaceOrder {
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side: OrderSide::Back,
+            amount: Uint128::new(1000),
+            odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), msg.clone());
+        assert_eq!(res.unwrap_err(), ContractError::MarketNotActive {});
+
+        // Once resumed the kept order matches again
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::ResumeMarket { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResumeMarket { market_id }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), msg).unwrap();
+        assert_eq!(attr(&res, "matched_amount"), "1000");
+
+        let res = execute(deps.as_mut(), env, mock_info(ADMIN, &[]), ExecuteMsg::ResumeMarket { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::MarketNotSuspended {});
     }
 
-    fn create_active_market(deps: DepsMut, env: Env) -> u64 {
+    fn create_future_market(deps: DepsMut, env: &Env, opening_auction: bool) -> u64 {
         let msg = ExecuteMsg::CreateMarket { 
             category: "Sports".to_string(),
-            question: "Who will win the World Cup Final?".to_string(), // New field
-            description: "World Cup Final".to_string(),
+            question: "Who will win the World Cup Final?".to_string(),
+            description: "World Cup Final match details".to_string(),
             options: vec!["Team A".to_string(), "Team B".to_string()],
-            start_time: env.block.time.seconds().to_string(),
+            start_time: (env.block.time.seconds() + 100).to_string(),
             end_time: (env.block.time.seconds() + 10000).to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: Some(opening_auction),
         };
-        let info = mock_info(ADMIN, &[Coin {
-            denom: TOKEN_DENOM.to_string(),
-            amount: Uint128::new(500000),
-        }]);
-        let res = execute(deps, env, info, msg).unwrap();
-        res.attributes.iter().find(|attr| attr.key == "market_id").unwrap().value.parse().unwrap()
+        let res = execute(deps, env.clone(), mock_info(ADMIN, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(500000) }]), msg).unwrap();
+        attr(&res, "market_id").parse().unwrap()
+    }
+
+    #[test]
+    fn test_opening_auction() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        // Without an opening auction nothing trades before the start time
+        let market_id = create_future_market(deps.as_mut(), &env, false);
+        let msg = order_with_time_in_force(market_id, OrderSide::Back, 1000, 200, TimeInForce::GoodTilCancelled);
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]), msg);
+        assert_eq!(res.unwrap_err(), ContractError::MarketNotOpen {});
+
+        // Crossing orders queue without matching until the open
+        let market_id = create_future_market(deps.as_mut(), &env, true);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER3, market_id, OrderSide::Back, 1000, 250);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1500, 300);
+        let res = place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 220);
+        assert_eq!(attr(&res, "matched_amount"), "0");
+        let msg = ExecuteMsg::PlaceOrder {
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Market { worst_odds: None },
+            side: OrderSide::Back,
+            amount: Uint128::new(1000),
+            odds: 0,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]), msg);
+        assert_eq!(res.unwrap_err(), ContractError::InvalidTimeInForce {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[]), ExecuteMsg::OpenMarket { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::MarketNotOpen {});
+
+        // 2.5 and 3.0 both match 1500 with 500 left over, the lower odds win the tie
+        let mut open_env = env.clone();
+        open_env.block.time = env.block.time.plus_seconds(100);
+        let res = execute(deps.as_mut(), open_env.clone(), mock_info(USER3, &[]), ExecuteMsg::OpenMarket { market_id }).unwrap();
+        assert_eq!(attr(&res, "auction"), "0:250:1500");
+
+        let bets: Vec<MatchedBet> = from_json(query(deps.as_ref(), open_env.clone(), QueryMsg::MatchedBets { market_id: Some(market_id), user: None, start_after: None, limit: None }).unwrap()).unwrap();
+        let bets: Vec<(&str, &str, u128, u32)> = bets.iter().map(|bet| (bet.back_user.as_str(), bet.lay_user.as_str(), bet.amount.u128(), bet.odds)).collect();
+        assert_eq!(bets, vec![(USER1, USER2, 1000, 250), (USER3, USER2, 500, 250)]);
+
+        // The layer gets back the liability locked above the auction odds
+        assert_eq!(balance(deps.as_ref(), USER2), Uint128::new(750));
+        let order: Order = from_json(query(deps.as_ref(), open_env.clone(), QueryMsg::Order { order_id: 2 }).unwrap()).unwrap();
+        assert_eq!((order.status, order.filled_amount), (OrderStatus::PartiallyFilled, Uint128::new(500)));
+        let book: OrderBook = from_json(query(deps.as_ref(), open_env.clone(), QueryMsg::OrderBook { market_id, option_id: 0, depth: None }).unwrap()).unwrap();
+        assert_eq!((book.best_back, book.best_lay, book.last_matched_odds), (Some(250), Some(220), Some(250)));
+
+        let res = execute(deps.as_mut(), open_env.clone(), mock_info(USER3, &[]), ExecuteMsg::OpenMarket { market_id });
+        assert_eq!(res.unwrap_err(), ContractError::InvalidMarketState {});
+
+        // Otherwise the first order after the start time runs the auction
+        let market_id = create_future_market(deps.as_mut(), &env, true);
+        place_limit_order(deps.as_mut(), &env, USER1, market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, USER2, market_id, OrderSide::Lay, 1000, 200);
+        place_limit_order(deps.as_mut(), &open_env, USER3, market_id, OrderSide::Back, 1000, 400);
+        let bets: Vec<MatchedBet> = from_json(query(deps.as_ref(), open_env.clone(), QueryMsg::MatchedBets { market_id: Some(market_id), user: None, start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(bets.len(), 1);
+
+        // An option suspended before the open keeps its queued orders and is uncrossed when it resumes
+        let market_id = create_future_market(deps.as_mut(), &env, true);
+        place_limit_order(deps.as_mut(), &env, "bettor1", market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, "bettor2", market_id, OrderSide::Lay, 1000, 200);
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::SuspendOption { market_id, option_id: 0 }).unwrap();
+        let res = execute(deps.as_mut(), open_env.clone(), mock_info(USER3, &[]), ExecuteMsg::OpenMarket { market_id }).unwrap();
+        assert!(res.attributes.iter().all(|attr| attr.key != "auction"));
+        assert_eq!(balance(deps.as_ref(), "bettor1"), Uint128::zero());
+        let book: OrderBook = from_json(query(deps.as_ref(), open_env.clone(), QueryMsg::OrderBook { market_id, option_id: 0, depth: None }).unwrap()).unwrap();
+        assert_eq!((book.best_back, book.best_lay), (Some(200), Some(200)));
+
+        let res = execute(deps.as_mut(), open_env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::ResumeOption { market_id, option_id: 0 }).unwrap();
+        assert_eq!(attr(&res, "auction"), "0:200:1000");
+        let book: OrderBook = from_json(query(deps.as_ref(), open_env.clone(), QueryMsg::OrderBook { market_id, option_id: 0, depth: None }).unwrap()).unwrap();
+        assert_eq!((book.best_back, book.best_lay, book.last_matched_odds), (None, None, Some(200)));
+
+        // A voided option's queued orders are refunded and it takes no part in the auction
+        let market_id = create_future_market(deps.as_mut(), &env, true);
+        place_limit_order(deps.as_mut(), &env, "bettor3", market_id, OrderSide::Back, 1000, 200);
+        place_limit_order(deps.as_mut(), &env, "bettor4", market_id, OrderSide::Lay, 1000, 200);
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::VoidOption { market_id, option_id: 0 }).unwrap();
+        let res = execute(deps.as_mut(), open_env.clone(), mock_info(USER3, &[]), ExecuteMsg::OpenMarket { market_id }).unwrap();
+        assert!(res.attributes.iter().all(|attr| attr.key != "auction"));
+        assert_eq!(balance(deps.as_ref(), "bettor3"), Uint128::new(1000));
+        assert_eq!(balance(deps.as_ref(), "bettor4"), Uint128::new(1000));
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let market_id = create_open_market(deps.as_mut(), &env);
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::Deposit {});
+        assert_eq!(res.unwrap_err(), ContractError::NoFundsSent {});
+        let other = Coin { denom: "uother".to_string(), amount: Uint128::new(5000) };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(5000) }, other.clone()]), ExecuteMsg::Deposit {});
+        assert_eq!(res.unwrap_err(), ContractError::InvalidFunds {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(5000) }]), ExecuteMsg::Deposit {}).unwrap();
+        assert_eq!(attr(&res, "balance"), "5000");
+
+        // Orders lock collateral from the balance without any funds attached
+        let order = |side: OrderSide, odds: u32| ExecuteMsg::PlaceOrder {
+            market_id,
+            option_id: 0,
+            order_type: OrderType::Limit,
+            side,
+            amount: Uint128::new(2000),
+            odds,
+            time_in_force: None,
+            keep_on_suspend: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), order(OrderSide::Back, 300)).unwrap();
+        assert_eq!(attr(&res, "balance"), "3000");
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), order(OrderSide::Lay, 200)).unwrap();
+        assert_eq!(attr(&res, "locked_amount"), "2000");
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(1000));
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), order(OrderSide::Back, 250));
+        assert_eq!(res.unwrap_err(), ContractError::InsufficientFunds {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[other]), order(OrderSide::Back, 250));
+        assert_eq!(res.unwrap_err(), ContractError::InvalidFunds {});
+
+        // Cancelling credits the collateral back to the balance instead of sending it
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::CancelOrder { order_id: 1 }).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(3000));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::Withdraw { amount: Uint128::zero() });
+        assert_eq!(res.unwrap_err(), ContractError::ZeroAmount {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), ExecuteMsg::Withdraw { amount: Uint128::new(1) });
+        assert_eq!(res.unwrap_err(), ContractError::InsufficientFunds {});
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), ExecuteMsg::Withdraw { amount: Uint128::new(2500) }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER1.to_string(),
+            amount: vec![Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(2500) }],
+        }));
+        assert_eq!(attr(&res, "balance"), "500");
+
+        // Closing the market returns the resting lay liability to the balance
+        let mut close_env = env.clone();
+        close_env.block.time = env.block.time.plus_seconds(10001);
+        execute(deps.as_mut(), close_env, mock_info(ADMIN, &[]), ExecuteMsg::CloseMarket { market_id }).unwrap();
+        assert_eq!(balance(deps.as_ref(), USER1), Uint128::new(2500));
     }
 
     #[test]
@@ -2145,6 +6452,8 @@ mod tests {
             side: OrderSide::Back,
             amount: Uint128::new(1000),
             odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let _ = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]), back_bet_msg).unwrap();
 
@@ -2156,6 +6465,8 @@ mod tests {
             side: OrderSide::Lay,
             amount: Uint128::new(1000),
             odds: 150,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let _ = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(500) }]), lay_bet_msg).unwrap();
 
@@ -2241,6 +6552,8 @@ mod tests {
             side: OrderSide::Back,
             amount: Uint128::new(1000),
             odds: 9900, // Maximum allowed odds
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let res = execute(deps.as_mut(), env, mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(1000) }]), place_bet_msg);
         assert!(res.is_ok());
@@ -2253,10 +6566,10 @@ mod tests {
         setup_contract(deps.as_mut());
 
         // Enable whitelist
-        let update_config_msg = ExecuteMsg::UpdateConfig { 
-            field: "whitelist_enabled".to_string(), 
-            value: "true".to_string() 
-        };
+        let update_config_msg = ExecuteMsg::UpdateConfig(ConfigUpdate { 
+            whitelist_enabled: Some(true),
+            ..ConfigUpdate::default()
+        });
         let _ = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), update_config_msg).unwrap();
 
         // Try to create market with non-whitelisted user (should fail)
@@ -2269,6 +6582,7 @@ mod tests {
             end_time: (env.block.time.seconds() + 10000).to_string(),
             resolution_bond: Uint128::new(1000000),
             resolution_reward: Uint128::new(500000),
+            opening_auction: None,
         };
         let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), create_market_msg.clone());
         assert!(res.is_err());
@@ -2336,6 +6650,8 @@ mod tests {
             side: OrderSide::Back,
             amount: Uint128::new(70000000),
             odds: 220, // 2.2 in percentage format
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let _ = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(70000000) }]), back_order_msg).unwrap();
 
@@ -2347,6 +6663,8 @@ mod tests {
             side: OrderSide::Lay,
             amount: Uint128::new(50000000),
             odds: 150, // 1.5 in percentage format
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(25000000) }]), lay_order_msg).unwrap();
 
@@ -2379,6 +6697,8 @@ mod tests {
             side: OrderSide::Lay,
             amount: Uint128::new(70000000),
             odds: 220, // 2.2 in percentage format
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(84000000) }]), matching_lay_order_msg).unwrap();
 
@@ -2416,6 +6736,8 @@ mod tests {
             side: OrderSide::Back,
             amount: Uint128::new(100_000_000),
             odds: 220,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let _ = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(100_000_000) }]), back_order_msg1).unwrap();
 
@@ -2427,6 +6749,8 @@ mod tests {
             side: OrderSide::Lay,
             amount: Uint128::new(10_000_000),
             odds: 170,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let _ = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(7_000_000) }]), lay_order_msg).unwrap();
 
@@ -2438,6 +6762,8 @@ mod tests {
             side: OrderSide::Back,
             amount: Uint128::new(100_000_000),
             odds: 300,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let res = execute(deps.as_mut(), env.clone(), mock_info(USER3, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(100_000_000) }]), back_order_msg2).unwrap();
 
@@ -2483,6 +6809,8 @@ mod tests {
             side: OrderSide::Back,
             amount: Uint128::new(100000000),
             odds: 200,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let _ = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(100000000) }]), back_order_msg).unwrap();
 
@@ -2494,6 +6822,8 @@ mod tests {
             side: OrderSide::Lay,
             amount: Uint128::new(50000000),
             odds: 210,
+            time_in_force: None,
+            keep_on_suspend: None,
         };
         let res = execute(deps.as_mut(), env.clone(), mock_info(USER2, &[Coin { denom: TOKEN_DENOM.to_string(), amount: Uint128::new(55000000) }]), lay_order_msg).unwrap();
 
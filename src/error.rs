@@ -30,6 +30,21 @@ pub enum ContractError {
     #[error("Market is not active")]
     MarketNotActive {},
 
+    #[error("Option does not exist in this market")]
+    InvalidOption {},
+
+    #[error("Option is not open for trading")]
+    OptionNotActive {},
+
+    #[error("Option is not suspended")]
+    OptionNotSuspended {},
+
+    #[error("Market is not open for trading yet")]
+    MarketNotOpen {},
+
+    #[error("Market is not suspended")]
+    MarketNotSuspended {},
+
     #[error("Market is closed")]
     MarketClosed {},
 
@@ -42,9 +57,27 @@ pub enum ContractError {
     #[error("No funds sent with the transaction")]
     NoFundsSent {},
 
+    #[error("Funds must be sent in the token denom")]
+    InvalidFunds {},
+
     #[error("Insufficient funds for the operation")]
     InsufficientFunds {},
 
+    #[error("Amount must be greater than zero")]
+    ZeroAmount {},
+
+    #[error("Fill-or-kill order could not be fully matched")]
+    OrderNotFilled {},
+
+    #[error("Post-only order would match immediately")]
+    PostOnlyWouldMatch {},
+
+    #[error("Order expiry must be in the future")]
+    InvalidExpiry {},
+
+    #[error("Time in force is not supported for this order type")]
+    InvalidTimeInForce {},
+
     #[error("Order cannot be cancelled")]
     OrderNotCancellable {},
 
@@ -54,6 +87,12 @@ pub enum ContractError {
     #[error("Winnings have already been redeemed")]
     AlreadyRedeemed {},
 
+    #[error("No winnings to redeem")]
+    NothingToRedeem {},
+
+    #[error("Position cannot be cashed out")]
+    CashOutUnavailable {},
+
     #[error("Challenge period has ended")]
     ChallengePeriodEnded {},
 
@@ -99,7 +138,7 @@ pub enum ContractError {
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 
-    #[error("Invalid vote value. Must be 0 or 1")]
+    #[error("Invalid vote value. Must be a valid option or the void outcome")]
     InvalidVote {},
 
     #[error("Invalid resolution bond amount")]
@@ -111,6 +150,108 @@ pub enum ContractError {
     #[error("Invalid period for {field}")]
     InvalidPeriod { field: String },
 
+    #[error("Invalid token denom")]
+    InvalidDenom {},
+
+    #[error("Cannot migrate from a different contract: {contract}")]
+    InvalidContractName { contract: String },
+
+    #[error("Invalid contract version: {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    CannotDowngrade { from: String, to: String },
+
+    #[error("No voting power at the dispute's stake snapshot")]
+    NoVotingPower {},
+
+    #[error("No unstaked tokens to claim")]
+    NothingToClaim {},
+
+    #[error("No voter reward to claim")]
+    NoVoterReward {},
+
+    #[error("Votes must be committed and revealed for this dispute")]
+    CommitRevealRequired {},
+
+    #[error("Dispute is not voted with commit-reveal")]
+    CommitRevealNotEnabled {},
+
+    #[error("No committed vote to reveal")]
+    NoCommittedVote {},
+
+    #[error("Revealed vote does not match the committed hash")]
+    InvalidReveal {},
+
+    #[error("Vote hash must be a hex encoded sha256 digest")]
+    InvalidVoteHash {},
+
+    #[error("Reveal period has not started")]
+    RevealPeriodNotStarted {},
+
+    #[error("Reveal period has ended")]
+    RevealPeriodEnded {},
+
+    #[error("Supermajority cannot exceed 10000 basis points")]
+    InvalidDisputeRules {},
+
+    #[error("Dispute has been escalated to the resolvers")]
+    DisputeEscalated {},
+
+    #[error("Appeal period has not ended")]
+    AppealPeriodNotEnded {},
+
+    #[error("Appeal period has ended")]
+    AppealPeriodEnded {},
+
+    #[error("Dispute round has not been decided or cannot be appealed")]
+    NotAppealable {},
+
+    #[error("Appeal must propose a different outcome than the round result")]
+    InvalidAppeal {},
+
+    #[error("Bond multiplier must be at least 1")]
+    InvalidBondMultiplier {},
+
+    #[error("Dispute has not been escalated")]
+    DisputeNotEscalated {},
+
+    #[error("Bond split shares must add up to 10000 basis points")]
+    InvalidBondSplit {},
+
+    #[error("Bond has already been redeemed")]
+    BondAlreadyRedeemed {},
+
+    #[error("Contract is paused: {scope}")]
+    Paused { scope: String },
+
+    #[error("Address already has the role")]
+    RoleAlreadyGranted {},
+
+    #[error("Address does not have the role")]
+    RoleNotGranted {},
+
+    #[error("No pending admin transfer")]
+    NoPendingAdmin {},
+
+    #[error("Admin transfer has expired")]
+    AdminTransferExpired {},
+
+    #[error("No config fields to update")]
+    EmptyConfigUpdate {},
+
+    #[error("Token denom cannot change while the contract holds funds")]
+    DenomInUse {},
+
+    #[error("Cannot set and clear the guardian in the same update")]
+    ConflictingGuardianUpdate {},
+
+    #[error("Platform fee cannot exceed 10000 basis points")]
+    InvalidPlatformFee {},
+
+    #[error("No fees pending for this market")]
+    NoPendingFees {},
+
     #[error("Minimum bet amount cannot be zero")]
     InvalidMinBet {},
 
@@ -1,6 +1,6 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Uint128};
-use crate::state:: OrderSide;
+use crate::state::{BondSplit, DisputeRules, EscalationConfig, OrderSide, PauseScope, Role, StakingConfig, TimeInForce, VoteMode};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
@@ -14,14 +14,62 @@ pub struct InstantiateMsg {
     pub voting_period: u64,
     pub min_bet: Uint128,
     pub whitelist_enabled: bool,
+    pub guardian: Option<Addr>,
+    /// Defaults to `BondSplit::default()`
+    pub bond_split: Option<BondSplit>,
+    /// Defaults to `DisputeRules::default()`
+    pub dispute_rules: Option<DisputeRules>,
+    /// Defaults to `VoteMode::Open`
+    pub vote_mode: Option<VoteMode>,
+    /// Defaults to `StakingConfig::default()`
+    pub staking: Option<StakingConfig>,
+    /// Defaults to `EscalationConfig::default()`, which allows no appeals
+    pub escalation: Option<EscalationConfig>,
+    /// Defaults to 0, basis points of a disputed market's platform fees paid to its voters
+    pub voter_fee_share: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Config changes applied once the state has been migrated
+    pub config: Option<ConfigUpdate>,
+}
+
+/// Config fields to change, fields left out keep their current value
+#[cw_serde]
+#[derive(Default)]
+pub struct ConfigUpdate {
+    pub token_denom: Option<String>,
+    pub platform_fee: Option<Uint128>,
+    pub treasury: Option<Addr>,
+    pub challenging_period: Option<u64>,
+    pub voting_period: Option<u64>,
+    pub min_bet: Option<Uint128>,
+    pub whitelist_enabled: Option<bool>,
+    pub guardian: Option<Addr>,
+    /// Removes the guardian, cannot be combined with `guardian`
+    pub clear_guardian: Option<bool>,
+    pub bond_split: Option<BondSplit>,
+    pub dispute_rules: Option<DisputeRules>,
+    pub vote_mode: Option<VoteMode>,
+    pub staking: Option<StakingConfig>,
+    pub escalation: Option<EscalationConfig>,
+    pub voter_fee_share: Option<u64>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    UpdateConfig { field: String, value: String },
+    UpdateConfig(ConfigUpdate),
+    /// Starts an admin transfer that `new_admin` has to accept, optionally before `expires_at`
+    ProposeNewAdmin { new_admin: Addr, expires_at: Option<u64> },
+    AcceptAdmin {},
+    CancelAdminTransfer {},
+    /// Pauses one scope, or every scope when none is given. Admin or guardian only.
+    Pause { scope: Option<PauseScope> },
+    /// Lifts the pause of one scope, or of every scope when none is given. Admin or guardian only.
+    Unpause { scope: Option<PauseScope> },
+    GrantRole { role: Role, address: Addr },
+    RevokeRole { role: Role, address: Addr },
     CreateMarket {
         category: String,
         question: String,
@@ -31,9 +79,18 @@ pub enum ExecuteMsg {
         end_time: String,   
         resolution_bond: Uint128,
         resolution_reward: Uint128,
+        /// Queue orders placed before `start_time` and match them in a single-price auction at the open
+        opening_auction: Option<bool>,
     },
     CancelMarket { market_id: u64 },
     CloseMarket { market_id: u64 },
+    /// Runs the opening auction of a market once its start time has passed
+    OpenMarket { market_id: u64 },
+    SuspendMarket { market_id: u64 },
+    ResumeMarket { market_id: u64 },
+    SuspendOption { market_id: u64, option_id: u8 },
+    ResumeOption { market_id: u64, option_id: u8 },
+    VoidOption { market_id: u64, option_id: u8 },
     ProposeResult { market_id: u64, winning_outcome: u8 },
     PlaceOrder {
         market_id: u64,
@@ -42,9 +99,16 @@ pub enum ExecuteMsg {
         side: OrderSide,
         amount: Uint128,
         odds: u32,
+        time_in_force: Option<TimeInForce>,
+        /// Keep the order resting when the market is suspended instead of cancelling it
+        keep_on_suspend: Option<bool>,
     },
     CancelOrder { order_id: u64 },
     RedeemWinnings { matched_bet_id: u64 },
+    /// Redeems every winning bet of the sender in a resolved market
+    RedeemMarket { market_id: u64 },
+    /// Redeems the sender's winning bets across all resolved markets, a page of matched bets at a time
+    RedeemAll { start_after: Option<u64>, limit: Option<u32> },
     AddToWhitelist { address: Addr },
     RemoveFromWhitelist { address: Addr },
     RaiseDispute {
@@ -52,31 +116,83 @@ pub enum ExecuteMsg {
         proposed_outcome: u8,
         evidence: String,
     },
+    /// `outcome` is an option index, or `VOID_OUTCOME` to vote for refunding the market
     CastVote { market_id: u64, outcome: u8 },
+    /// Commits to a vote in commit-reveal mode, `hash` is the hex encoded sha256 of
+    /// "{market_id}:{voter}:{outcome}:{salt}"
+    CommitVote { market_id: u64, hash: String },
+    /// Reveals a committed vote once the voting period is over and before the reveal window closes
+    RevealVote { market_id: u64, outcome: u8, salt: String },
     ResolveDispute { market_id: u64 },
+    /// Challenges the result of a decided round within the appeal period, opening the next round.
+    /// The bond is the previous round's bond times `bond_multiplier`.
+    AppealDispute {
+        market_id: u64,
+        proposed_outcome: u8,
+        evidence: String,
+    },
+    /// Decides a dispute whose vote was escalated or that reached its final round, `outcome` can be `VOID_OUTCOME`
+    ResolveEscalatedDispute { market_id: u64, outcome: u8 },
     RedeemBondAmount { market_id: u64 }, // Fix Bug ID #2
+    /// Sends the sender's share of the voter reward pool of a resolved dispute
+    ClaimVoterReward { market_id: u64 },
+    SweepFees { market_id: u64 },
+    /// Places the hedge quoted by `CashOutQuote` as a fill-or-kill market order.
+    /// `worst_odds` bounds the slippage and defaults to the quoted odds.
+    CashOut { market_id: u64, option_id: u8, worst_odds: Option<u32> },
+    /// Stakes the attached `token_denom` for stake-weighted dispute voting
+    Stake {},
+    /// Starts unbonding staked tokens, they can be claimed after the unbonding period
+    Unstake { amount: Uint128 },
+    /// Sends the sender's tokens whose unbonding period has ended
+    ClaimUnstaked {},
+    Deposit {},
+    Withdraw { amount: Uint128 },
 }
 
 #[cw_serde]
 pub enum OrderType {
     Limit,
-    Market,
+    /// Immediate-or-cancel: sweeps the best opposite-side odds and refunds any unmatched
+    /// remainder instead of resting. `worst_odds` is the lowest odds a back order accepts
+    /// (highest for a lay order); the `odds` of the order are ignored.
+    Market { worst_odds: Option<u32> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingAdmin {},
+    /// Whether the address holds the role, which is always the case for the admin
+    HasRole { role: Role, address: Addr },
+    /// Roles granted to the address
+    Roles { address: Addr },
+    RoleMembers { role: Role, start_after: Option<String>, limit: Option<u32> },
     Market { market_id: u64 },
     Markets { status: Option<String>, start_after: Option<u64>, limit: Option<u32> },
     Order { order_id: u64 },
-    UserOrders { user: Addr, market_id: Option<u64>, start_after: Option<u64>, limit: Option<u32> },
+    UserOrders { user: Addr, market_id: Option<u64>, status: Option<String>, start_after: Option<u64>, limit: Option<u32> },
     MarketOrders { market_id: u64, side: Option<String>, start_after: Option<u64>, limit: Option<u32> },
     MatchedBets { market_id: Option<u64>, user: Option<Addr>, start_after: Option<u64>, limit: Option<u32> },
     ResolutionProposal { market_id: u64 },
     Dispute { market_id: u64 },
+    /// Votes and tallies, hidden until the reveal window of a commit-reveal dispute has closed
     Votes { market_id: u64 },
+    /// Every round of the dispute with its bond, result and votes
+    DisputeRounds { market_id: u64 },
+    /// Voter reward pool of the dispute and the voter's claimable share
+    VoterReward { market_id: u64, voter: Addr },
     IsWhitelisted { user: Addr },
     MarketStatistics { market_id: u64 },
     WhitelistedAddresses { start_after: Option<String>, limit: Option<u32> },
+    MarketFees { market_id: u64 },
+    OrderBook { market_id: u64, option_id: u8, depth: Option<u32> },
+    Balance { user: Addr },
+    Stake { address: Addr },
+    /// Voting power of the address in the dispute of the market
+    VotingPower { market_id: u64, address: Addr },
+    Position { user: Addr, market_id: u64, option_id: u8 },
+    Positions { user: Addr, market_id: u64 },
+    CashOutQuote { user: Addr, market_id: u64, option_id: u8 },
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Empty, Int128, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, SnapshotMap, Strategy};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -14,8 +14,280 @@ pub struct Config {
     pub voting_period: u64,
     pub min_bet: Uint128,
     pub whitelist_enabled: bool,
+    /// Address that can pause and unpause the contract besides the admin
+    pub guardian: Option<Addr>,
+    pub paused: PauseFlags,
+    /// How the bond of the losing side of a dispute is distributed
+    pub bond_split: BondSplit,
+    pub dispute_rules: DisputeRules,
+    pub vote_mode: VoteMode,
+    pub staking: StakingConfig,
+    pub escalation: EscalationConfig,
+    /// Share of the platform fees of a disputed market in basis points added to its voter reward pool
+    pub voter_fee_share: u64,
 }
 
+/// Appeals of dispute round results. The round opened by the last allowed appeal is decided by the resolvers.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscalationConfig {
+    /// Number of appeals allowed per dispute, 0 makes the first vote final
+    pub max_appeals: u32,
+    /// Each appeal bond is the previous round's bond times this factor
+    pub bond_multiplier: u64,
+    /// Seconds after a round is decided during which it can be appealed
+    pub appeal_period: u64,
+}
+
+impl Default for EscalationConfig {
+    fn default() -> Self {
+        EscalationConfig {
+            max_appeals: 0,
+            bond_multiplier: 2,
+            appeal_period: 86400,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingConfig {
+    /// Seconds unstaked tokens stay locked before they can be claimed
+    pub unbonding_period: u64,
+    /// Weight dispute votes by the voter's stake at the dispute's creation instead of
+    /// giving every whitelisted address one vote
+    pub stake_weighted_voting: bool,
+    /// Share of their voting stake in basis points slashed from voters on the losing side, 0 disables slashing
+    pub slash_rate: u64,
+}
+
+impl Default for StakingConfig {
+    fn default() -> Self {
+        StakingConfig {
+            unbonding_period: 14 * 86400,
+            stake_weighted_voting: false,
+            slash_rate: 0,
+        }
+    }
+}
+
+/// Unstaked tokens waiting for the end of the unbonding period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Unbonding {
+    pub amount: Uint128,
+    pub release_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakeResponse {
+    pub address: Addr,
+    pub staked: Uint128,
+    pub unbonding: Vec<Unbonding>,
+    pub total_staked: Uint128,
+}
+
+/// How dispute votes are cast, fixed for a dispute when it is raised
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub enum VoteMode {
+    /// Votes are cast in the clear with `CastVote`
+    #[default]
+    Open,
+    /// Voters commit a hash of their vote during the voting period and reveal it within
+    /// `reveal_period` seconds after it. Unrevealed commits are not counted.
+    CommitReveal { reveal_period: u64 },
+}
+
+impl fmt::Display for VoteMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoteMode::Open => write!(f, "open"),
+            VoteMode::CommitReveal { reveal_period } => write!(f, "commit_reveal:{}", reveal_period),
+        }
+    }
+}
+
+/// How the votes of a dispute decide the market result
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DisputeRules {
+    /// Minimum number of votes for the vote to count
+    pub quorum: u64,
+    /// Share of the votes in basis points the leading outcome needs, 0 means a plurality wins
+    pub supermajority: u64,
+    /// Applies when outcomes tie for the most votes or the leading outcome misses the supermajority
+    pub tie_rule: FallbackRule,
+    pub no_quorum_rule: FallbackRule,
+}
+
+impl Default for DisputeRules {
+    fn default() -> Self {
+        DisputeRules {
+            quorum: 1,
+            supermajority: 0,
+            tie_rule: FallbackRule::OriginalStands,
+            no_quorum_rule: FallbackRule::OriginalStands,
+        }
+    }
+}
+
+/// Resolution of a dispute whose vote is not decisive
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum FallbackRule {
+    /// The proposed result stands
+    OriginalStands,
+    /// Resolvers decide the result with `ResolveEscalatedDispute`
+    Escalate,
+    /// The market is resolved to `VOID_OUTCOME` and all matched bets are refunded
+    Void,
+}
+
+impl FallbackRule {
+    /// Result the rule resolves to, `None` when the dispute is escalated
+    pub fn outcome(&self, proposed_result: u8) -> Option<u8> {
+        match self {
+            FallbackRule::OriginalStands => Some(proposed_result),
+            FallbackRule::Escalate => None,
+            FallbackRule::Void => Some(VOID_OUTCOME),
+        }
+    }
+}
+
+impl fmt::Display for FallbackRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FallbackRule::OriginalStands => write!(f, "original_stands"),
+            FallbackRule::Escalate => write!(f, "escalate"),
+            FallbackRule::Void => write!(f, "void"),
+        }
+    }
+}
+
+/// Shares of a slashed bond in basis points, adding up to 10000
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondSplit {
+    pub winner: u64,
+    /// Split evenly between the voters who voted for the winning outcome
+    pub voters: u64,
+    pub treasury: u64,
+}
+
+impl Default for BondSplit {
+    fn default() -> Self {
+        BondSplit { winner: 5000, voters: 3000, treasury: 2000 }
+    }
+}
+
+/// Operations halted by an emergency pause. Withdrawals, cancellations and refunds are never paused.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PauseFlags {
+    pub trading: bool,
+    pub market_creation: bool,
+    pub redemptions: bool,
+    pub disputes: bool,
+}
+
+impl PauseFlags {
+    pub fn is_paused(&self, scope: PauseScope) -> bool {
+        match scope {
+            PauseScope::Trading => self.trading,
+            PauseScope::MarketCreation => self.market_creation,
+            PauseScope::Redemptions => self.redemptions,
+            PauseScope::Disputes => self.disputes,
+        }
+    }
+
+    pub fn set(&mut self, scope: PauseScope, paused: bool) {
+        match scope {
+            PauseScope::Trading => self.trading = paused,
+            PauseScope::MarketCreation => self.market_creation = paused,
+            PauseScope::Redemptions => self.redemptions = paused,
+            PauseScope::Disputes => self.disputes = paused,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub enum PauseScope {
+    /// Placing orders, cashing out and running opening auctions
+    Trading,
+    MarketCreation,
+    /// Redeeming winnings and resolution bonds
+    Redemptions,
+    /// Proposing results, raising disputes, voting and resolving
+    Disputes,
+}
+
+impl PauseScope {
+    pub const ALL: [PauseScope; 4] = [
+        PauseScope::Trading,
+        PauseScope::MarketCreation,
+        PauseScope::Redemptions,
+        PauseScope::Disputes,
+    ];
+}
+
+impl fmt::Display for PauseScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PauseScope::Trading => write!(f, "trading"),
+            PauseScope::MarketCreation => write!(f, "market_creation"),
+            PauseScope::Redemptions => write!(f, "redemptions"),
+            PauseScope::Disputes => write!(f, "disputes"),
+        }
+    }
+}
+
+/// Privileges the admin can delegate, the admin itself holds all of them
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub enum Role {
+    /// Closes, suspends and resumes markets and their options
+    Operator,
+    /// Finalizes resolution proposals and disputes
+    Resolver,
+    /// Adds and removes whitelisted addresses
+    WhitelistManager,
+    /// Sweeps collected fees to the treasury
+    FeeManager,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Operator, Role::Resolver, Role::WhitelistManager, Role::FeeManager];
+
+    /// Discriminant used to key role assignments
+    pub fn key(&self) -> u8 {
+        match self {
+            Role::Operator => 0,
+            Role::Resolver => 1,
+            Role::WhitelistManager => 2,
+            Role::FeeManager => 3,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Operator => write!(f, "operator"),
+            Role::Resolver => write!(f, "resolver"),
+            Role::WhitelistManager => write!(f, "whitelist_manager"),
+            Role::FeeManager => write!(f, "fee_manager"),
+        }
+    }
+}
+
+/// Admin transfer waiting to be accepted by `new_admin`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingAdmin {
+    pub new_admin: Addr,
+    pub expires_at: Option<u64>,
+}
+
+impl PendingAdmin {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
+
+/// Dispute vote and market result meaning that no option won and all matched bets are refunded
+pub const VOID_OUTCOME: u8 = u8::MAX;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Market {
     pub id: u64,
@@ -23,6 +295,7 @@ pub struct Market {
     pub question: String, 
     pub description: String,
     pub options: Vec<String>,
+    pub option_statuses: Vec<OptionStatus>,
     pub category: String,
     pub start_time: u64,
     pub end_time: u64,
@@ -30,11 +303,38 @@ pub struct Market {
     pub resolution_bond: Uint128,
     pub resolution_reward: Uint128,
     pub result: Option<u8>,
+    /// Orders placed before `start_time` are queued for an opening auction that has not run yet
+    pub opening_auction: bool,
+    /// Platform fee in basis points when the market was created. Collateral released early and
+    /// the fees withheld at settlement use this rate, so later fee changes cannot unbalance them.
+    pub platform_fee: Uint128,
+}
+
+impl Market {
+    /// Whether the market can be resolved to the option: it must exist and not be void
+    pub fn can_resolve_to(&self, option_id: u8) -> bool {
+        matches!(
+            self.option_statuses.get(option_id as usize),
+            Some(OptionStatus::Active) | Some(OptionStatus::Suspended)
+        )
+    }
+}
+
+/// Trading state of a single market option
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum OptionStatus {
+    Active,
+    /// No new orders are accepted, resting orders stay on the book without matching
+    Suspended,
+    /// The option's bets are refunded and it cannot be the result of the market
+    Void,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum MarketStatus {
     Active,
+    /// Trading is paused, for instance while an event is in play
+    Suspended,
     Closed,
     Canceled,
     InDispute,
@@ -53,6 +353,31 @@ pub struct Order {
     pub filled_amount: Uint128,
     pub status: OrderStatus,
     pub timestamp: u64,
+    pub time_in_force: TimeInForce,
+    /// Whether the order stays on the book when the market is suspended
+    pub keep_on_suspend: bool,
+}
+
+impl Order {
+    /// Good-til-date orders are treated as cancelled once their expiry has passed
+    pub fn is_expired(&self, now: u64) -> bool {
+        match self.time_in_force {
+            TimeInForce::GoodTilDate { expires_at } => now >= expires_at,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum TimeInForce {
+    /// Rests on the book until filled or cancelled
+    GoodTilCancelled,
+    /// Reverts unless the whole amount is matched immediately
+    FillOrKill,
+    /// Rejected if any part of it would match immediately
+    PostOnly,
+    /// Rests on the book until `expires_at` (seconds)
+    GoodTilDate { expires_at: u64 },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -82,6 +407,75 @@ pub struct MatchedBet {
     pub redeemed: bool,
 }
 
+/// Net exposure of a user on one market option, aggregated over all of their matched bets
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Position {
+    pub user: Addr,
+    pub market_id: u64,
+    pub option_id: u8,
+    /// Stake of the user's back bets
+    pub back_stake: Uint128,
+    /// Pot the back bets pay out if the option wins
+    pub back_payout: Uint128,
+    /// Backers' stake matched by the user's lay bets
+    pub lay_stake: Uint128,
+    /// Liability of the user's lay bets
+    pub lay_liability: Uint128,
+    /// Collateral handed back early because it is no longer at risk
+    pub released: Uint128,
+    /// Part of `released` already deducted from redemptions and refunds
+    pub reclaimed: Uint128,
+}
+
+impl Position {
+    pub fn new(user: Addr, market_id: u64, option_id: u8) -> Self {
+        Position {
+            user,
+            market_id,
+            option_id,
+            back_stake: Uint128::zero(),
+            back_payout: Uint128::zero(),
+            lay_stake: Uint128::zero(),
+            lay_liability: Uint128::zero(),
+            released: Uint128::zero(),
+            reclaimed: Uint128::zero(),
+        }
+    }
+
+    /// Collateral the user put up for the matched bets
+    pub fn collateral(&self) -> Uint128 {
+        self.back_stake + self.lay_liability
+    }
+
+    /// Total the user receives if the option wins, net of the fee on profit
+    pub fn payout_if_win(&self, platform_fee: Uint128) -> Uint128 {
+        let fee = (self.back_payout - self.back_stake).multiply_ratio(platform_fee, 10000u128);
+        self.back_payout - fee
+    }
+
+    /// Total the user receives if the option loses, net of the fee on profit
+    pub fn payout_if_lose(&self, platform_fee: Uint128) -> Uint128 {
+        let fee = self.lay_stake.multiply_ratio(platform_fee, 10000u128);
+        self.lay_stake + self.lay_liability - fee
+    }
+
+    /// Collateral that comes back to the user whatever the outcome, including a refund.
+    /// Anything above the amount already released can be handed back before settlement.
+    pub fn releasable(&self, platform_fee: Uint128) -> Uint128 {
+        self.payout_if_win(platform_fee)
+            .min(self.payout_if_lose(platform_fee))
+            .min(self.collateral())
+    }
+
+    pub fn pnl_if_win(&self, platform_fee: Uint128) -> Int128 {
+        Int128::new(self.payout_if_win(platform_fee).u128() as i128 - self.collateral().u128() as i128)
+    }
+
+    pub fn pnl_if_lose(&self, platform_fee: Uint128) -> Int128 {
+        Int128::new(self.payout_if_lose(platform_fee).u128() as i128 - self.collateral().u128() as i128)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ResolutionProposal {
     pub market_id: u64,
@@ -91,6 +485,11 @@ pub struct ResolutionProposal {
     pub proposal_time: u64,
     pub challenge_deadline: u64,
     pub status: ProposalStatus,
+    /// Share of the loser's bond paid to the winner of the dispute along with their own bond
+    #[serde(default)]
+    pub winner_bond_share: Uint128,
+    #[serde(default)]
+    pub bond_redeemed: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -104,8 +503,20 @@ pub enum ProposalStatus {
 pub struct Vote {
     pub voter: Addr,
     pub option_id: u8,
+    /// Voting power counted for the vote, the voter's stake with stake-weighted voting
+    #[serde(default = "Vote::default_weight")]
+    pub weight: u64,
+}
+
+impl Vote {
+    fn default_weight() -> u64 {
+        1
+    }
 }
 
+/// Votes of a dispute with the number of votes per outcome
+pub type VotesResponse = (Vec<Vote>, Vec<(u8, u64)>);
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Dispute {
     pub market_id: u64,
@@ -114,11 +525,95 @@ pub struct Dispute {
     pub evidence: String,
     pub status: DisputeStatus,
     pub created_at: u64,
+    /// End of the round's voting period, fixed when the round opens
+    #[serde(default)]
+    pub voting_deadline: Option<u64>,
+    /// End of the reveal window when the dispute is voted with commit-reveal
+    #[serde(default)]
+    pub reveal_deadline: Option<u64>,
+    /// Block height of the stake snapshot giving the voting power, when votes are stake-weighted
+    #[serde(default)]
+    pub snapshot_height: Option<u64>,
+    /// Current round, `challenger`, `proposed_outcome`, `evidence` and `created_at` are the round's
+    #[serde(default = "Dispute::first_round")]
+    pub round: u32,
+    /// Appeals allowed when the dispute was raised
+    #[serde(default)]
+    pub max_appeals: u32,
+    /// End of the appeal window of a decided round
+    #[serde(default)]
+    pub appeal_deadline: Option<u64>,
+}
+
+impl Dispute {
+    fn first_round() -> u32 {
+        1
+    }
+
+    /// End of the round's voting period. Disputes raised before it was stored use `voting_period`
+    pub fn voting_end(&self, voting_period: u64) -> u64 {
+        self.voting_deadline.unwrap_or(self.created_at + voting_period)
+    }
+}
+
+/// A round of a dispute: the raised dispute is round 1 and each appeal opens the next one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DisputeRound {
+    pub market_id: u64,
+    pub round: u32,
+    pub challenger: Addr,
+    /// Outcome challenged by the round, the proposed result or the previous round's result
+    pub challenged_outcome: u8,
+    pub proposed_outcome: u8,
+    pub evidence: String,
+    pub bond: Uint128,
+    pub created_at: u64,
+    /// Outcome the round was decided with, None while open or when left to the resolvers
+    pub result: Option<u8>,
+    /// Votes of the round, recorded when it closes
+    pub votes: Vec<Vote>,
+    pub vote_counts: Vec<(u8, u64)>,
+    /// Share of the losing bonds paid to the challenger along with their bond
+    pub winner_bond_share: Uint128,
+    pub bond_redeemed: bool,
+}
+
+/// Rewards of the voters for the winning outcome of a resolved dispute, shared pro rata to their voting weight
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoterRewardPool {
+    pub market_id: u64,
+    pub winning_outcome: u8,
+    /// Voters' share of the slashed bonds
+    pub bond_rewards: Uint128,
+    /// Voters' share of the market's platform fees
+    pub fee_rewards: Uint128,
+    /// `voter_fee_share` when the dispute was resolved
+    pub fee_share: u64,
+    pub winning_weight: u64,
+}
+
+impl VoterRewardPool {
+    pub fn total(&self) -> Uint128 {
+        self.bond_rewards + self.fee_rewards
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoterRewardResponse {
+    pub market_id: u64,
+    pub voter: Addr,
+    pub claimable: Uint128,
+    pub claimed: Uint128,
+    pub pool: Option<VoterRewardPool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum DisputeStatus {
     Active,
+    /// The round's vote decided an outcome that can still be appealed
+    Decided,
+    /// The vote was not decisive or the final round was reached, the result is left to the resolvers
+    Escalated,
     Resolved,
 }
 
@@ -130,10 +625,70 @@ pub struct MarketStatistics {
     pub status: MarketStatus,
 }
 
+/// Unfilled stake resting at one odds level of the book
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceLevel {
+    pub odds: u32,
+    pub amount: Uint128,
+    pub order_count: u64,
+}
+
+/// Aggregated depth of one market option. Each side is listed best price first,
+/// so `best_back` is the lowest back odds on offer and `best_lay` the highest lay odds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OrderBook {
+    pub market_id: u64,
+    pub option_id: u8,
+    pub back: Vec<PriceLevel>,
+    pub lay: Vec<PriceLevel>,
+    pub best_back: Option<u32>,
+    pub best_lay: Option<u32>,
+    pub last_matched_odds: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionResponse {
+    pub position: Position,
+    pub pnl_if_win: Int128,
+    pub pnl_if_lose: Int128,
+}
+
+/// Hedge order that locks in the same profit whether the option wins or loses
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CashOutQuote {
+    pub market_id: u64,
+    pub option_id: u8,
+    /// Side of the hedge, None when the position is already flat or the book has no liquidity
+    pub side: Option<OrderSide>,
+    pub amount: Uint128,
+    /// Best odds on the book for the hedge
+    pub odds: u32,
+    /// Stake available at `odds`
+    pub available: Uint128,
+    /// Profit on every outcome once the hedge is matched, before fees
+    pub profit: Int128,
+}
+
+/// Positions of a user in a market with the net P&L for each outcome, indexed by option
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionsResponse {
+    pub market_id: u64,
+    pub positions: Vec<PositionResponse>,
+    pub outcome_pnl: Vec<Int128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketFees {
+    pub market_id: u64,
+    pub collected: Uint128,
+    pub pending: Uint128,
+}
+
 impl fmt::Display for MarketStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             MarketStatus::Active => write!(f, "Active"),
+            MarketStatus::Suspended => write!(f, "Suspended"),
             MarketStatus::Closed => write!(f, "Closed"),
             MarketStatus::Canceled => write!(f, "Canceled"),
             MarketStatus::InDispute => write!(f, "InDispute"),
@@ -142,6 +697,44 @@ impl fmt::Display for MarketStatus {
     }
 }
 
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderStatus::Open => write!(f, "Open"),
+            OrderStatus::PartiallyFilled => write!(f, "PartiallyFilled"),
+            OrderStatus::Filled => write!(f, "Filled"),
+            OrderStatus::Canceled => write!(f, "Canceled"),
+        }
+    }
+}
+
+impl OrderSide {
+    /// Discriminant used to key the order book by side
+    pub fn key(&self) -> u8 {
+        match self {
+            OrderSide::Back => 0,
+            OrderSide::Lay => 1,
+        }
+    }
+
+    /// Maps odds to a rank so that ranges over the book go best price first:
+    /// back orders with the lowest odds and lay orders with the highest odds
+    pub fn price_rank(&self, odds: u32) -> u32 {
+        match self {
+            OrderSide::Back => odds,
+            OrderSide::Lay => u32::MAX - odds,
+        }
+    }
+
+    /// Inverse of `price_rank`
+    pub fn odds_from_rank(&self, rank: u32) -> u32 {
+        match self {
+            OrderSide::Back => rank,
+            OrderSide::Lay => u32::MAX - rank,
+        }
+    }
+}
+
 impl fmt::Display for OrderSide {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -153,16 +746,128 @@ impl fmt::Display for OrderSide {
 
 
 pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+/// Role assignments keyed by `Role::key` and holder
+pub const ROLES: Map<(u8, Addr), Empty> = Map::new("roles");
 pub const MARKETS: Map<u64, Market> = Map::new("markets");
 pub const MARKET_COUNT: Item<u64> = Item::new("market_count");
-pub const ORDERS: Map<u64, Order> = Map::new("orders");
+pub struct OrderIndexes<'a> {
+    pub market: MultiIndex<'a, u64, Order, u64>,
+}
+
+impl<'a> IndexList<Order> for OrderIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Order>> + '_> {
+        let v: Vec<&dyn Index<Order>> = vec![&self.market];
+        Box::new(v.into_iter())
+    }
+}
+
+pub const ORDERS: IndexedMap<u64, Order, OrderIndexes> = IndexedMap::new(
+    "orders",
+    OrderIndexes {
+        market: MultiIndex::new(|_pk, order| order.market_id, "orders", "orders__market"),
+    },
+);
+/// (market_id, (option_id, side), (price_rank, order_id))
+pub type OrderBookKey = (u64, (u8, u8), (u32, u64));
+/// Resting orders of every market. An ascending range over one side walks it in price-time priority.
+pub const ORDER_BOOK: Map<OrderBookKey, Empty> = Map::new("order_book");
 pub const ORDER_COUNT: Item<u64> = Item::new("order_count");
-pub const USER_ORDERS: Map<(Addr, u64), Vec<u64>> = Map::new("user_orders");
-pub const MATCHED_BETS: Map<u64, MatchedBet> = Map::new("matched_bets");
+/// (user, order_id) -> market_id of every order placed by a user
+pub const USER_ORDERS: Map<(Addr, u64), u64> = Map::new("user_orders");
+pub struct MatchedBetIndexes<'a> {
+    pub market: MultiIndex<'a, u64, MatchedBet, u64>,
+}
+
+impl<'a> IndexList<MatchedBet> for MatchedBetIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<MatchedBet>> + '_> {
+        let v: Vec<&dyn Index<MatchedBet>> = vec![&self.market];
+        Box::new(v.into_iter())
+    }
+}
+
+pub const MATCHED_BETS: IndexedMap<u64, MatchedBet, MatchedBetIndexes> = IndexedMap::new(
+    "matched_bets",
+    MatchedBetIndexes {
+        market: MultiIndex::new(|_pk, bet| bet.market_id, "matched_bets", "matched_bets__market"),
+    },
+);
+/// (user, market_id, option_id) -> net position of the user on the option
+pub const POSITIONS: Map<(Addr, u64, u8), Position> = Map::new("positions");
+/// Internal token balance of each trader, funding orders and receiving refunds and winnings
+pub const BALANCES: Map<Addr, Uint128> = Map::new("balances");
+/// (market_id, option_id) -> odds of the most recent match on that option
+pub const LAST_MATCHED_ODDS: Map<(u64, u8), u32> = Map::new("last_matched_odds");
 pub const MATCHED_BET_COUNT: Item<u64> = Item::new("matched_bet_count");
-pub const USER_MATCHED_BETS: Map<(Addr, u64), Vec<u64>> = Map::new("user_matched_bets");
+/// (user, matched_bet_id) -> market_id of every matched bet a user is a side of
+pub const USER_MATCHED_BETS: Map<(Addr, u64), u64> = Map::new("user_matched_bets");
 pub const PROPOSALS: Map<u64, ResolutionProposal> = Map::new("proposals");
 pub const VOTES: Map<(u64, Addr), Vote> = Map::new("votes");
 pub const VOTE_COUNTS: Map<(u64, u8), u64> = Map::new("vote_counts");
+pub const STAKES: SnapshotMap<Addr, Uint128> = SnapshotMap::new(
+    "stakes",
+    "stakes__checkpoints",
+    "stakes__changelog",
+    Strategy::EveryBlock,
+);
+pub const TOTAL_STAKED: Item<Uint128> = Item::new("total_staked");
+pub const UNBONDING: Map<Addr, Vec<Unbonding>> = Map::new("unbonding");
+/// (market_id, voter) -> hex encoded sha256 of "{market_id}:{voter}:{outcome}:{salt}", until revealed
+pub const VOTE_COMMITS: Map<(u64, Addr), String> = Map::new("vote_commits");
 pub const WHITELISTED_ADDRESSES: Map<Addr, bool> = Map::new("whitelisted_addresses");
-pub const DISPUTES: Map<u64, Dispute> = Map::new("disputes");
\ No newline at end of file
+pub const DISPUTES: Map<u64, Dispute> = Map::new("disputes");
+pub const DISPUTE_ROUNDS: Map<(u64, u32), DisputeRound> = Map::new("dispute_rounds");
+pub const VOTER_REWARD_POOLS: Map<u64, VoterRewardPool> = Map::new("voter_reward_pools");
+/// Weight of a voter's votes for the winning outcome of a dispute
+pub const VOTER_REWARD_WEIGHTS: Map<(u64, Addr), u64> = Map::new("voter_reward_weights");
+pub const VOTER_REWARDS_CLAIMED: Map<(u64, Addr), Uint128> = Map::new("voter_rewards_claimed");
+pub const MARKET_FEES: Map<u64, MarketFees> = Map::new("market_fees");
+// Storage layout of v0.1.0, only read by `migrate`. The items share their namespaces with the current ones.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub admin: Addr,
+    pub token_denom: String,
+    pub platform_fee: Uint128,
+    pub treasury: Addr,
+    pub challenging_period: u64,
+    pub voting_period: u64,
+    pub min_bet: Uint128,
+    pub whitelist_enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyMarket {
+    pub id: u64,
+    pub creator: Addr,
+    pub question: String,
+    pub description: String,
+    pub options: Vec<String>,
+    pub category: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub status: MarketStatus,
+    pub resolution_bond: Uint128,
+    pub resolution_reward: Uint128,
+    pub result: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyOrder {
+    pub id: u64,
+    pub market_id: u64,
+    pub creator: Addr,
+    pub option_id: u8,
+    pub side: OrderSide,
+    pub amount: Uint128,
+    pub odds: u32,
+    pub filled_amount: Uint128,
+    pub status: OrderStatus,
+    pub timestamp: u64,
+}
+
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+pub const LEGACY_MARKETS: Map<u64, LegacyMarket> = Map::new("markets");
+pub const LEGACY_ORDERS: Map<u64, LegacyOrder> = Map::new("orders");
+pub const LEGACY_USER_ORDERS: Map<(Addr, u64), Vec<u64>> = Map::new("user_orders");
+pub const LEGACY_USER_MATCHED_BETS: Map<(Addr, u64), Vec<u64>> = Map::new("user_matched_bets");